@@ -0,0 +1,209 @@
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::settings::xdg_dir;
+
+const MANIFEST: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BackupFile {
+    /// Absolute path of the config file that was backed up.
+    pub original: PathBuf,
+    /// File name of the copy inside the backup set directory.
+    pub stored: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Manifest {
+    created: i64,
+    files: Vec<BackupFile>,
+    #[serde(default)]
+    before_restore: bool,
+    /// The main config the set was made for; older sets start with it in `files`.
+    #[serde(default)]
+    config: Option<PathBuf>,
+}
+
+/// One timestamped copy of the main config and its sourced files.
+#[derive(Clone, Debug)]
+pub struct BackupSet {
+    pub dir: PathBuf,
+    pub created: i64,
+    pub files: Vec<BackupFile>,
+    /// The main config the set was made for, sets of each config being pruned separately.
+    pub config: PathBuf,
+    /// Made when restoring another set rather than when saving. Undo skips these, so
+    /// repeated undos step back through saves instead of toggling between two states.
+    pub before_restore: bool,
+}
+
+impl BackupSet {
+    /// Creation time formatted in local time, e.g. `2024-10-18 14:03:52`.
+    pub fn created_label(&self) -> String {
+        glib::DateTime::from_unix_local(self.created)
+            .and_then(|dt| dt.format("%Y-%m-%d %H:%M:%S"))
+            .map(|s| s.to_string())
+            .unwrap_or_else(|_| self.created.to_string())
+    }
+
+    /// Read every file of the set along with the path it belongs to.
+    pub fn contents(&self) -> io::Result<Vec<(PathBuf, String)>> {
+        self.files
+            .iter()
+            .map(|file| {
+                let content = fs::read_to_string(self.dir.join(&file.stored))?;
+                Ok((file.original.clone(), content))
+            })
+            .collect()
+    }
+
+    /// Whether every file of the set still has the content it was backed up with.
+    pub fn matches_disk(&self) -> bool {
+        self.contents().is_ok_and(|contents| {
+            contents
+                .iter()
+                .all(|(path, content)| fs::read_to_string(path).is_ok_and(|c| c == *content))
+        })
+    }
+
+    pub fn delete(&self) -> io::Result<()> {
+        fs::remove_dir_all(&self.dir)
+    }
+}
+
+pub fn backups_dir() -> PathBuf {
+    xdg_dir("XDG_STATE_HOME", ".local/state")
+        .join("hyprgui")
+        .join("backups")
+}
+
+/// Copy the given files, the main config first, into a new backup set and prune the sets
+/// of that config beyond `limit`. `before_restore` marks a set made right before restoring
+/// another one.
+pub fn create(paths: &[PathBuf], limit: usize, before_restore: bool) -> io::Result<BackupSet> {
+    let now = glib::DateTime::now_local().map_err(io::Error::other)?;
+    let stamp = now
+        .format("%Y%m%d-%H%M%S")
+        .map_err(io::Error::other)?
+        .to_string();
+
+    let root = backups_dir();
+    let mut dir = root.join(&stamp);
+    let mut suffix = 1;
+    while dir.exists() {
+        dir = root.join(format!("{}-{}", stamp, suffix));
+        suffix += 1;
+    }
+    fs::create_dir_all(&dir)?;
+
+    let mut files = Vec::new();
+    for (idx, path) in paths.iter().enumerate() {
+        if !path.exists() {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let stored = format!("{}-{}", idx, name);
+        fs::copy(path, dir.join(&stored))?;
+        files.push(BackupFile {
+            original: fs::canonicalize(path).unwrap_or_else(|_| path.clone()),
+            stored,
+        });
+    }
+
+    let config = paths
+        .first()
+        .map(|path| fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+        .unwrap_or_default();
+    let manifest = Manifest {
+        created: now.to_unix(),
+        files,
+        before_restore,
+        config: Some(config.clone()),
+    };
+    let json = serde_json::to_string_pretty(&manifest).map_err(io::Error::other)?;
+    fs::write(dir.join(MANIFEST), json)?;
+
+    prune(&config, limit)?;
+
+    Ok(BackupSet {
+        dir,
+        created: manifest.created,
+        files: manifest.files,
+        config,
+        before_restore,
+    })
+}
+
+/// All backup sets, newest first.
+pub fn list() -> Vec<BackupSet> {
+    let mut sets: Vec<BackupSet> = fs::read_dir(backups_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let content = fs::read_to_string(dir.join(MANIFEST)).ok()?;
+            let manifest: Manifest = serde_json::from_str(&content).ok()?;
+            let config = manifest
+                .config
+                .or_else(|| manifest.files.first().map(|file| file.original.clone()))
+                .unwrap_or_default();
+            Some(BackupSet {
+                dir,
+                created: manifest.created,
+                files: manifest.files,
+                config,
+                before_restore: manifest.before_restore,
+            })
+        })
+        .collect();
+    sets.sort_by(|a, b| b.created.cmp(&a.created).then(b.dir.cmp(&a.dir)));
+    sets
+}
+
+/// Keep the newest `limit` sets made on save for `config`, and as many made before a
+/// restore, so restoring doesn't push saves out of the history.
+fn prune(config: &Path, limit: usize) -> io::Result<()> {
+    let sets: Vec<BackupSet> = list().into_iter().filter(|s| s.config == config).collect();
+    for before_restore in [false, true] {
+        for set in sets
+            .iter()
+            .filter(|s| s.before_restore == before_restore)
+            .skip(limit.max(1))
+        {
+            set.delete()?;
+        }
+    }
+    Ok(())
+}
+
+/// Write through a temporary file and rename it over the target, so a crash never
+/// leaves a truncated config behind. Symlinks are followed so dotfile links survive.
+pub fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let file_name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp = target.with_file_name(format!(".{}.hyprgui-tmp", file_name));
+
+    let result = (|| {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(&target) {
+            fs::set_permissions(&tmp, metadata.permissions())?;
+        }
+        fs::rename(&tmp, &target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::ipc::{self, HyprlandRequests, SocketClient};
+
+/// One error Hyprland reported while parsing the config.
+#[derive(Clone, Debug)]
+pub struct ConfigError {
+    pub path: Option<PathBuf>,
+    /// 1-based line number, as Hyprland reports it.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ConfigError {
+    /// `file:line: message`, or just the message when the location is unknown.
+    pub fn label(&self) -> String {
+        match (&self.path, self.line) {
+            (Some(path), Some(line)) => format!("{}:{}: {}", path.display(), line, self.message),
+            _ => self.message.clone(),
+        }
+    }
+}
+
+/// Make Hyprland reload its config, which matters when `misc:disable_autoreload` is set.
+pub async fn reload(client: Option<Arc<SocketClient>>) -> Result<(), String> {
+    let client = client.ok_or("Hyprland is not running")?;
+    ipc::call(client, |c| c.reload())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The errors of the last config parse, from `configerrors`.
+pub async fn query(client: Option<Arc<SocketClient>>) -> Vec<ConfigError> {
+    let Some(client) = client else {
+        return Vec::new();
+    };
+    let Ok(errors) = ipc::call(client, |c| c.config_errors()).await else {
+        return Vec::new();
+    };
+    errors
+        .iter()
+        .flat_map(|s| s.lines())
+        .filter(|s| !s.trim().is_empty())
+        .map(parse)
+        .collect()
+}
+
+/// Split `Config error in file /path/hyprland.conf at line 12: message` into its parts.
+fn parse(text: &str) -> ConfigError {
+    let text = text.trim();
+    if let Some(rest) = text.split_once("in file ").map(|(_, rest)| rest)
+        && let Some((path, rest)) = rest.split_once(" at line ")
+        && let Some((line, message)) = rest.split_once(':')
+        && let Ok(line) = line.trim().parse::<usize>()
+    {
+        return ConfigError {
+            path: Some(PathBuf::from(path.trim())),
+            line: Some(line),
+            message: message.trim().to_string(),
+        };
+    }
+    ConfigError {
+        path: None,
+        line: None,
+        message: text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_errors_into_file_line_and_message() {
+        let error = parse(
+            "Config error in file /home/u/.config/hypr/hyprland.conf at line 12: invalid field gaps_in: 1 2 x",
+        );
+        assert_eq!(
+            error.path,
+            Some(PathBuf::from("/home/u/.config/hypr/hyprland.conf"))
+        );
+        assert_eq!(error.line, Some(12));
+        assert_eq!(error.message, "invalid field gaps_in: 1 2 x");
+        assert_eq!(
+            error.label(),
+            "/home/u/.config/hypr/hyprland.conf:12: invalid field gaps_in: 1 2 x"
+        );
+    }
+
+    #[test]
+    fn keeps_errors_without_a_location_whole() {
+        let error = parse("  source= globbing error: found no match ");
+        assert_eq!((error.path, error.line), (None, None));
+        assert_eq!(error.message, "source= globbing error: found no match");
+    }
+}
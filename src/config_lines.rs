@@ -0,0 +1,331 @@
+use hyprparser::HyprlandConfig;
+use std::path::Path;
+
+const INDENT: &str = "    ";
+
+/// Where an option is defined and the value it has there.
+#[derive(Clone, Debug)]
+pub struct OptionLocation {
+    /// `None` for the main config file, otherwise an index into `sourced_content`.
+    pub source: Option<usize>,
+    pub value: String,
+}
+
+/// Map a GUI option to its section path and key, e.g. `("decoration", "blur:size")`
+/// becomes `(["decoration", "blur"], "size")`.
+pub fn option_path(category: &str, name: &str) -> (Vec<String>, String) {
+    let parts: Vec<&str> = name.split(':').collect();
+    // The "layouts" page maps to the top-level "dwindle" and "master" sections.
+    if category == "layouts" && parts.len() == 2 {
+        return (vec![parts[0].to_string()], parts[1].to_string());
+    }
+    let mut section = vec![category.to_string()];
+    section.extend(parts[..parts.len() - 1].iter().map(|p| p.to_string()));
+    (section, parts[parts.len() - 1].to_string())
+}
+
+/// Hyprland's name for a GUI option, e.g. `decoration:blur:size`.
+pub fn full_key(category: &str, name: &str) -> String {
+    let (section, key) = option_path(category, name);
+    format!("{}:{}", section.join(":"), key)
+}
+
+pub fn lines(config: &HyprlandConfig, source: Option<usize>) -> Option<&Vec<String>> {
+    match source {
+        None => Some(&config.content),
+        Some(idx) => config.sourced_content.get(idx),
+    }
+}
+
+pub fn lines_mut(config: &mut HyprlandConfig, source: Option<usize>) -> Option<&mut Vec<String>> {
+    match source {
+        None => Some(&mut config.content),
+        Some(idx) => config.sourced_content.get_mut(idx),
+    }
+}
+
+/// Join lines back into file content, keeping the original file's trailing newline.
+pub fn join_lines(lines: &[String], original: &str) -> String {
+    let mut content = lines.join("\n");
+    if original.ends_with('\n') {
+        content.push('\n');
+    }
+    content
+}
+
+/// Human readable name of a config file, with `$HOME` shortened to `~`.
+pub fn source_label(config: &HyprlandConfig, source: Option<usize>) -> String {
+    let path = match source {
+        None => return "hyprland.conf".to_string(),
+        Some(idx) => config.sourced_paths.get(idx).cloned().unwrap_or_default(),
+    };
+    match std::env::var("HOME") {
+        Ok(home) if !home.is_empty() && path.starts_with(&home) => {
+            format!("~{}", &path[home.len()..])
+        }
+        _ => path,
+    }
+}
+
+/// The line of the main file with the `source =` line each sourced file was read from,
+/// by index into `sourced_content`. Files are read in the order of those lines, skipping
+/// the ones that can't be read, so each line is matched to the next file of its name.
+pub fn source_lines(config: &HyprlandConfig) -> Vec<Option<usize>> {
+    let mut positions = vec![None; config.sourced_paths.len()];
+    let mut next = 0;
+    for (idx, line) in config.content.iter().enumerate() {
+        let Some(path) = config.sourced_paths.get(next) else {
+            break;
+        };
+        let trimmed = line.trim();
+        if !trimmed.starts_with("source") {
+            continue;
+        }
+        let Some((_, value)) = trimmed.split_once('=') else {
+            continue;
+        };
+        let value = value.split('#').next().unwrap_or(value).trim();
+        if Path::new(value).file_name() == Path::new(path).file_name() {
+            positions[next] = Some(idx);
+            next += 1;
+        }
+    }
+    positions
+}
+
+/// Find the definition of an option that takes effect: the last one Hyprland reads, with
+/// each sourced file read at its `source =` line.
+pub fn locate_option(
+    config: &HyprlandConfig,
+    section: &[String],
+    key: &str,
+) -> Option<OptionLocation> {
+    // Ordered by the main file line read last before the definition, a sourced file
+    // coming right after its `source =` line
+    let mut found: Option<((usize, usize), OptionLocation)> = None;
+    let mut consider = |order: (usize, usize), source: Option<usize>, value: String| {
+        if found.as_ref().is_none_or(|(last, _)| order > *last) {
+            found = Some((order, OptionLocation { source, value }));
+        }
+    };
+    if let Some((idx, value)) = find_option(&config.content, section, key) {
+        consider((idx, 0), None, value);
+    }
+    for (source, position) in source_lines(config).into_iter().enumerate() {
+        if let Some(lines) = config.sourced_content.get(source)
+            && let Some((_, value)) = find_option(lines, section, key)
+        {
+            // Files without a recognised `source =` line are read last, in their order
+            let order = match position {
+                Some(idx) => (idx, 1),
+                None => (usize::MAX, 1 + source),
+            };
+            consider(order, Some(source), value);
+        }
+    }
+    found.map(|(_, location)| location)
+}
+
+/// Find the last `key = value` inside the nested `section { }` blocks of one file, the
+/// one Hyprland applies.
+pub fn find_option(lines: &[String], section: &[String], key: &str) -> Option<(usize, String)> {
+    find_options(lines, section, key).pop()
+}
+
+/// Every `key = value` inside the nested `section { }` blocks of one file. Keys written
+/// with colons (`blur:size = 3` inside `decoration { }`, or `decoration:blur:size = 3`)
+/// match too.
+pub fn find_options(lines: &[String], section: &[String], key: &str) -> Vec<(usize, String)> {
+    let wanted = format!("{}:{}", section.join(":"), key);
+    let mut stack: Vec<String> = Vec::new();
+    let mut found = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let trimmed = strip_comment(line).trim();
+        if let Some(name) = trimmed.strip_suffix('{') {
+            stack.push(name.trim().to_string());
+        } else if trimmed == "}" {
+            stack.pop();
+        } else if let Some((k, v)) = trimmed.split_once('=') {
+            let mut full = stack.clone();
+            full.push(k.trim().to_string());
+            if full.join(":") == wanted {
+                found.push((idx, v.trim().to_string()));
+            }
+        }
+    }
+    found
+}
+
+/// Set `key = value` in a file, rewriting the existing line or inserting it into the
+/// section, creating any missing `section { }` blocks.
+pub fn set_option(lines: &mut Vec<String>, section: &[String], key: &str, value: &str) {
+    if let Some((idx, _)) = find_option(lines, section, key) {
+        lines[idx] = replace_value(&lines[idx], value);
+        return;
+    }
+
+    let entry = format!("{} = {}", key, value);
+    let (depth, insert_at, indent) = (1..=section.len())
+        .rev()
+        .find_map(|depth| {
+            find_block(lines, &section[..depth]).map(|(start, end)| {
+                let indent = &lines[start][..lines[start].len() - lines[start].trim_start().len()];
+                (depth, end, format!("{}{}", indent, INDENT))
+            })
+        })
+        .unwrap_or_else(|| (0, lines.len(), String::new()));
+
+    let missing = &section[depth..];
+    let mut block = Vec::new();
+    if depth == 0 && lines.last().is_some_and(|l| !l.trim().is_empty()) {
+        block.push(String::new());
+    }
+    for (i, name) in missing.iter().enumerate() {
+        block.push(format!("{}{}{} {{", indent, INDENT.repeat(i), name));
+    }
+    block.push(format!(
+        "{}{}{}",
+        indent,
+        INDENT.repeat(missing.len()),
+        entry
+    ));
+    for i in (0..missing.len()).rev() {
+        block.push(format!("{}{}}}", indent, INDENT.repeat(i)));
+    }
+    lines.splice(insert_at..insert_at, block);
+}
+
+/// Delete the `key = value` line of an option, returning whether it was found.
+pub fn remove_option(lines: &mut Vec<String>, section: &[String], key: &str) -> bool {
+    match find_option(lines, section, key) {
+        Some((idx, _)) => {
+            lines.remove(idx);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Replace the value of a `key = value` line, keeping its indentation, spacing and comment.
+pub fn replace_value(original: &str, value: &str) -> String {
+    let Some((head, rest)) = original.split_once('=') else {
+        return original.to_string();
+    };
+    let spacing = &rest[..rest.len() - rest.trim_start().len()];
+    let comment = rest
+        .find('#')
+        .map(|idx| format!(" {}", &rest[idx..]))
+        .unwrap_or_default();
+    format!("{}={}{}{}", head, spacing, value, comment)
+}
+
+/// The line range of the first `a { b { ... } }` block matching `path`, from the opening
+/// line to its closing brace.
+fn find_block(lines: &[String], path: &[String]) -> Option<(usize, usize)> {
+    let mut stack: Vec<(String, usize)> = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let trimmed = strip_comment(line).trim();
+        if let Some(name) = trimmed.strip_suffix('{') {
+            stack.push((name.trim().to_string(), idx));
+        } else if trimmed == "}" {
+            if stack.iter().map(|(n, _)| n).eq(path.iter()) {
+                return stack.last().map(|(_, start)| (*start, idx));
+            }
+            stack.pop();
+        }
+    }
+    None
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split('#').next().unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    fn parsed(main: &str, sourced: &[(&str, &str)]) -> HyprlandConfig {
+        let mut config = HyprlandConfig::new();
+        config.content = lines(main);
+        config.sourced_paths = sourced.iter().map(|(path, _)| path.to_string()).collect();
+        config.sourced_content = sourced.iter().map(|(_, text)| lines(text)).collect();
+        config
+    }
+
+    fn section(name: &str) -> Vec<String> {
+        vec![name.to_string()]
+    }
+
+    #[test]
+    fn locates_the_definition_read_last() {
+        let main = "general {\n    gaps_in = 2\n}\nsource = ~/a.conf\ngeneral:gaps_in = 3\nsource = ~/b.conf";
+        let a = ("/home/u/a.conf", "general:gaps_in = 4");
+        let config = parsed(
+            main,
+            &[a, ("/home/u/b.conf", "general {\n    gaps_in = 5\n}")],
+        );
+        let location = locate_option(&config, &section("general"), "gaps_in").unwrap();
+        assert_eq!(location.source, Some(1));
+        assert_eq!(location.value, "5");
+
+        // a.conf is read before the main file's second definition
+        let config = parsed(main, &[a, ("/home/u/b.conf", "")]);
+        let location = locate_option(&config, &section("general"), "gaps_in").unwrap();
+        assert_eq!(location.source, None);
+        assert_eq!(location.value, "3");
+    }
+
+    #[test]
+    fn finds_every_definition_in_a_file() {
+        let file =
+            lines("decoration {\n    blur {\n        size = 3\n    }\n}\ndecoration:blur:size = 8");
+        let wanted = vec!["decoration".to_string(), "blur".to_string()];
+        assert_eq!(
+            find_options(&file, &wanted, "size"),
+            [(2, "3".to_string()), (5, "8".to_string())]
+        );
+        assert_eq!(
+            find_option(&file, &wanted, "size"),
+            Some((5, "8".to_string()))
+        );
+        assert!(find_option(&file, &section("decoration"), "size").is_none());
+    }
+
+    #[test]
+    fn sets_options_in_place_or_in_their_section() {
+        let mut file = lines("general {\n    gaps_in = 2 # inner\n}");
+        set_option(&mut file, &section("general"), "gaps_in", "4");
+        set_option(&mut file, &section("general"), "gaps_out", "8");
+        assert_eq!(
+            file,
+            lines("general {\n    gaps_in = 4 # inner\n    gaps_out = 8\n}")
+        );
+    }
+
+    #[test]
+    fn creates_missing_sections() {
+        let mut file = lines("decoration {\n    rounding = 4\n}");
+        let blur = vec!["decoration".to_string(), "blur".to_string()];
+        set_option(&mut file, &blur, "size", "3");
+        set_option(&mut file, &section("input"), "kb_layout", "us");
+        assert_eq!(
+            file,
+            lines(
+                "decoration {\n    rounding = 4\n    blur {\n        size = 3\n    }\n}\n\ninput {\n    kb_layout = us\n}"
+            )
+        );
+    }
+
+    #[test]
+    fn removes_only_the_option_line() {
+        let mut file = lines("general {\n    gaps_in = 2\n    gaps_out = 8\n}");
+        assert!(remove_option(&mut file, &section("general"), "gaps_in"));
+        assert!(!remove_option(&mut file, &section("general"), "gaps_in"));
+        assert_eq!(file, lines("general {\n    gaps_out = 8\n}"));
+    }
+}
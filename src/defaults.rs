@@ -0,0 +1,295 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::ipc::{self, HyprlandRequests, SocketClient};
+
+/// Hyprland's built-in defaults for the options HyprGUI exposes, used when Hyprland's
+/// `descriptions` reply is unavailable. Keys use Hyprland's `section:subsection:key` form.
+const BUILTIN_DEFAULTS: &[(&str, &str)] = &[
+    ("general:layout", "dwindle"),
+    ("general:gaps_in", "5"),
+    ("general:gaps_out", "20"),
+    ("general:gaps_workspaces", "0"),
+    ("general:border_size", "1"),
+    ("general:resize_corner", "0"),
+    ("general:no_border_on_floating", "false"),
+    ("general:resize_on_border", "false"),
+    ("general:extend_border_grab_area", "15"),
+    ("general:hover_icon_on_border", "true"),
+    ("general:col.inactive_border", "0xff444444"),
+    ("general:col.active_border", "0xffffffff"),
+    ("general:col.nogroup_border", "0xffffaaff"),
+    ("general:col.nogroup_border_active", "0xffff00ff"),
+    ("decoration:rounding", "0"),
+    ("decoration:active_opacity", "1.0"),
+    ("decoration:inactive_opacity", "1.0"),
+    ("decoration:fullscreen_opacity", "1.0"),
+    ("decoration:drop_shadow", "true"),
+    ("decoration:shadow_range", "4"),
+    ("decoration:shadow_render_power", "3"),
+    ("decoration:shadow_ignore_window", "true"),
+    ("decoration:col.shadow", "0xee1a1a1a"),
+    ("decoration:shadow_offset", "0 0"),
+    ("decoration:shadow_scale", "1.0"),
+    ("decoration:dim_inactive", "false"),
+    ("decoration:dim_strength", "0.5"),
+    ("decoration:dim_special", "0.2"),
+    ("decoration:dim_around", "0.4"),
+    ("decoration:screen_shader", ""),
+    ("decoration:blur:enabled", "true"),
+    ("decoration:blur:size", "8"),
+    ("decoration:blur:passes", "1"),
+    ("decoration:blur:ignore_opacity", "true"),
+    ("decoration:blur:new_optimizations", "true"),
+    ("decoration:blur:xray", "false"),
+    ("decoration:blur:noise", "0.0117"),
+    ("decoration:blur:contrast", "0.8916"),
+    ("decoration:blur:brightness", "0.8172"),
+    ("decoration:blur:vibrancy", "0.1696"),
+    ("decoration:blur:vibrancy_darkness", "0.0"),
+    ("decoration:blur:special", "false"),
+    ("decoration:blur:popups", "false"),
+    ("decoration:blur:popups_ignorealpha", "0.2"),
+    ("animations:enabled", "true"),
+    ("animations:first_launch_animation", "true"),
+    ("input:kb_model", ""),
+    ("input:kb_layout", "us"),
+    ("input:kb_variant", ""),
+    ("input:kb_options", ""),
+    ("input:kb_rules", ""),
+    ("input:kb_file", ""),
+    ("input:numlock_by_default", "false"),
+    ("input:resolve_binds_by_sym", "false"),
+    ("input:repeat_rate", "25"),
+    ("input:repeat_delay", "600"),
+    ("input:sensitivity", "0.0"),
+    ("input:accel_profile", ""),
+    ("input:force_no_accel", "false"),
+    ("input:left_handed", "false"),
+    ("input:scroll_method", ""),
+    ("input:scroll_button", "0"),
+    ("input:scroll_button_lock", "false"),
+    ("input:scroll_factor", "1.0"),
+    ("input:natural_scroll", "false"),
+    ("input:follow_mouse", "1"),
+    ("input:mouse_refocus", "true"),
+    ("input:scroll_points", ""),
+    ("input:focus_on_close", "0"),
+    ("input:float_switch_override_focus", "1"),
+    ("input:special_fallthrough", "false"),
+    ("input:touchpad:disable_while_typing", "true"),
+    ("input:touchpad:natural_scroll", "false"),
+    ("input:touchpad:scroll_factor", "1.0"),
+    ("input:touchpad:middle_button_emulation", "false"),
+    ("input:touchpad:tap_button_map", ""),
+    ("input:touchpad:clickfinger_behavior", "false"),
+    ("input:touchpad:tap-to-click", "true"),
+    ("input:touchpad:drag_lock", "false"),
+    ("input:touchpad:tap-and-drag", "false"),
+    ("input:touchdevice:transform", "0"),
+    ("input:touchdevice:output", "[[Auto]]"),
+    ("input:touchdevice:enabled", "true"),
+    ("input:tablet:transform", "0"),
+    ("input:tablet:output", ""),
+    ("input:tablet:region_position", "0 0"),
+    ("input:tablet:region_size", "0 0"),
+    ("input:tablet:relative_input", "false"),
+    ("input:tablet:left_handed", "false"),
+    ("input:tablet:active_area_size", "0 0"),
+    ("input:tablet:active_area_position", "0 0"),
+    ("input:off_window_axis_events", "1"),
+    ("input:emulate_discrete_scroll", "1"),
+    ("gestures:workspace_swipe", "false"),
+    ("gestures:workspace_swipe_fingers", "3"),
+    ("gestures:workspace_swipe_min_fingers", "false"),
+    ("gestures:workspace_swipe_distance", "300"),
+    ("gestures:workspace_swipe_touch", "false"),
+    ("gestures:workspace_swipe_invert", "true"),
+    ("gestures:workspace_swipe_touch_invert", "false"),
+    ("gestures:workspace_swipe_min_speed_to_force", "30"),
+    ("gestures:workspace_swipe_cancel_ratio", "0.5"),
+    ("gestures:workspace_swipe_create_new", "true"),
+    ("gestures:workspace_swipe_direction_lock", "true"),
+    ("gestures:workspace_swipe_direction_lock_threshold", "10"),
+    ("gestures:workspace_swipe_forever", "false"),
+    ("gestures:workspace_swipe_use_r", "false"),
+    ("group:auto_group", "true"),
+    ("group:insert_after_current", "true"),
+    ("group:focus_removed_window", "true"),
+    ("group:drag_into_group", "1"),
+    ("group:merge_groups_on_drag", "true"),
+    ("group:merge_floated_into_tiled_on_groupbar", "false"),
+    ("group:col.border_active", "0x66ffff00"),
+    ("group:col.border_inactive", "0x66777700"),
+    ("group:col.border_locked_active", "0x66ff5500"),
+    ("group:col.border_locked_inactive", "0x66775500"),
+    ("group:groupbar:enabled", "true"),
+    ("group:groupbar:font_family", ""),
+    ("group:groupbar:font_size", "8"),
+    ("group:groupbar:gradients", "true"),
+    ("group:groupbar:height", "14"),
+    ("group:groupbar:stacked", "false"),
+    ("group:groupbar:priority", "3"),
+    ("group:groupbar:render_titles", "true"),
+    ("group:groupbar:scrolling", "true"),
+    ("group:groupbar:text_color", "0xffffffff"),
+    ("group:groupbar:col.active", "0x66ffff00"),
+    ("group:groupbar:col.inactive", "0x66777700"),
+    ("group:groupbar:col.locked_active", "0x66ff5500"),
+    ("group:groupbar:col.locked_inactive", "0x66775500"),
+    ("misc:disable_hyprland_logo", "false"),
+    ("misc:disable_splash_rendering", "false"),
+    ("misc:col.splash", "0xffffffff"),
+    ("misc:font_family", "Sans"),
+    ("misc:splash_font_family", ""),
+    ("misc:force_default_wallpaper", "-1"),
+    ("misc:vfr", "true"),
+    ("misc:vrr", "0"),
+    ("misc:mouse_move_enables_dpms", "false"),
+    ("misc:key_press_enables_dpms", "false"),
+    ("misc:always_follow_on_dnd", "true"),
+    ("misc:layers_hog_keyboard_focus", "true"),
+    ("misc:animate_manual_resizes", "false"),
+    ("misc:animate_mouse_windowdragging", "false"),
+    ("misc:disable_autoreload", "false"),
+    ("misc:enable_swallow", "false"),
+    ("misc:swallow_regex", ""),
+    ("misc:swallow_exception_regex", ""),
+    ("misc:focus_on_activate", "false"),
+    ("misc:mouse_move_focuses_monitor", "true"),
+    ("misc:render_ahead_of_time", "false"),
+    ("misc:render_ahead_safezone", "1"),
+    ("misc:allow_session_lock_restore", "false"),
+    ("misc:background_color", "0xff111111"),
+    ("misc:close_special_on_empty", "true"),
+    ("misc:new_window_takes_over_fullscreen", "0"),
+    ("misc:exit_window_retains_fullscreen", "false"),
+    ("misc:initial_workspace_tracking", "1"),
+    ("misc:middle_click_paste", "true"),
+    ("misc:render_unfocused_fps", "15"),
+    ("misc:disable_xdg_env_checks", "false"),
+    ("binds:pass_mouse_when_bound", "false"),
+    ("binds:scroll_event_delay", "300"),
+    ("binds:workspace_back_and_forth", "false"),
+    ("binds:allow_workspace_cycles", "false"),
+    ("binds:workspace_center_on", "0"),
+    ("binds:focus_preferred_method", "0"),
+    ("binds:ignore_group_lock", "false"),
+    ("binds:movefocus_cycles_fullscreen", "true"),
+    ("binds:disable_keybind_grabbing", "false"),
+    ("binds:window_direction_monitor_fallback", "true"),
+    ("xwayland:enabled", "true"),
+    ("xwayland:use_nearest_neighbor", "true"),
+    ("xwayland:force_zero_scaling", "false"),
+    ("opengl:nvidia_anti_flicker", "true"),
+    ("opengl:force_introspection", "2"),
+    ("render:explicit_sync", "2"),
+    ("render:explicit_sync_kms", "2"),
+    ("render:direct_scanout", "false"),
+    ("cursor:sync_gsettings_theme", "true"),
+    ("cursor:no_hardware_cursors", "false"),
+    ("cursor:no_break_fs_vrr", "false"),
+    ("cursor:min_refresh_rate", "24"),
+    ("cursor:hotspot_padding", "1"),
+    ("cursor:inactive_timeout", "0"),
+    ("cursor:no_warps", "false"),
+    ("cursor:persistent_warps", "false"),
+    ("cursor:warp_on_change_workspace", "false"),
+    ("cursor:default_monitor", ""),
+    ("cursor:zoom_factor", "1.0"),
+    ("cursor:zoom_rigid", "false"),
+    ("cursor:enable_hyprcursor", "true"),
+    ("cursor:hide_on_key_press", "false"),
+    ("cursor:hide_on_touch", "true"),
+    ("cursor:allow_dumb_copy", "false"),
+    ("debug:overlay", "false"),
+    ("debug:damage_blink", "false"),
+    ("debug:disable_logs", "true"),
+    ("debug:disable_time", "true"),
+    ("debug:damage_tracking", "2"),
+    ("debug:enable_stdout_logs", "false"),
+    ("debug:manual_crash", "0"),
+    ("debug:suppress_errors", "false"),
+    ("debug:watchdog_timeout", "5"),
+    ("debug:disable_scale_checks", "false"),
+    ("debug:error_limit", "5"),
+    ("debug:error_position", "0"),
+    ("debug:colored_stdout_logs", "true"),
+    ("dwindle:pseudotile", "false"),
+    ("dwindle:force_split", "0"),
+    ("dwindle:preserve_split", "false"),
+    ("dwindle:smart_split", "false"),
+    ("dwindle:smart_resizing", "true"),
+    ("dwindle:permanent_direction_override", "false"),
+    ("dwindle:special_scale_factor", "1.0"),
+    ("dwindle:split_width_multiplier", "1.0"),
+    ("dwindle:use_active_for_splits", "true"),
+    ("dwindle:default_split_ratio", "1.0"),
+    ("dwindle:split_bias", "0"),
+    ("master:allow_small_split", "false"),
+    ("master:special_scale_factor", "1.0"),
+    ("master:mfact", "0.55"),
+    ("master:new_status", "slave"),
+    ("master:new_on_top", "false"),
+    ("master:new_on_active", "none"),
+    ("master:orientation", "left"),
+    ("master:inherit_fullscreen", "true"),
+    ("master:always_center_master", "false"),
+    ("master:smart_resizing", "true"),
+    ("master:drop_at_cursor", "true"),
+];
+
+/// Default values of Hyprland options, keyed by `section:subsection:key`.
+pub struct Defaults {
+    values: HashMap<String, String>,
+}
+
+impl Defaults {
+    /// The defaults HyprGUI ships with, for when no compositor is asked.
+    pub fn builtin() -> Self {
+        Self {
+            values: BUILTIN_DEFAULTS
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Built-in defaults, overridden by what the Hyprland instance of `client` reports
+    /// through its `descriptions` reply, since defaults change between releases.
+    pub async fn load(client: Option<Arc<SocketClient>>) -> Self {
+        let mut defaults = Self::builtin();
+        let Some(client) = client else {
+            return defaults;
+        };
+        if let Ok(json) = ipc::call(client, |c| c.descriptions()).await
+            && let Some(arr) = json.as_array()
+        {
+            for option in arr {
+                if let Some(key) = option.get("value").and_then(|v| v.as_str())
+                    && let Some(default) = option.get("data").and_then(|d| d.get("default"))
+                    && let Some(default) = format_default(key, default)
+                {
+                    defaults.values.insert(key.to_string(), default);
+                }
+            }
+        }
+        defaults
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|s| s.as_str())
+    }
+}
+
+fn format_default(key: &str, value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        // Colors are reported as plain ARGB integers
+        Value::Number(n) if key.contains("col.") => n.as_u64().map(|c| format!("0x{:08x}", c)),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,258 @@
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A unified diff (`diff -u` style) between two versions of a file, or an empty string if
+/// they are the same. A last line without a newline differs from the same line with one,
+/// and is followed by `\ No newline at end of file` like `diff` does.
+pub fn unified_diff(old: &str, new: &str, path: &str, context: usize) -> String {
+    // Lines keep their newline, so only the last one can lack it
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = diff_ops(&a, &b);
+
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (kind, _, _))| *kind != Kind::Equal)
+        .map(|(idx, _)| idx)
+        .collect();
+    let Some(&first) = changes.first() else {
+        return String::new();
+    };
+
+    // Group changes whose surrounding context overlaps into one hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    let mut start = first.saturating_sub(context);
+    let mut end = (first + context + 1).min(ops.len());
+    for &idx in &changes[1..] {
+        if idx.saturating_sub(context) <= end {
+            end = (idx + context + 1).min(ops.len());
+        } else {
+            hunks.push((start, end));
+            start = idx.saturating_sub(context);
+            end = (idx + context + 1).min(ops.len());
+        }
+    }
+    hunks.push((start, end));
+
+    let mut out = format!("--- {}\n+++ {}\n", path, path);
+    for (start, end) in hunks {
+        let hunk = &ops[start..end];
+        let old_len = hunk.iter().filter(|(k, _, _)| *k != Kind::Insert).count();
+        let new_len = hunk.iter().filter(|(k, _, _)| *k != Kind::Delete).count();
+        let (_, old_start, new_start) = hunk[0];
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_len == 0 {
+                old_start
+            } else {
+                old_start + 1
+            },
+            old_len,
+            if new_len == 0 {
+                new_start
+            } else {
+                new_start + 1
+            },
+            new_len
+        ));
+        for &(kind, i, j) in hunk {
+            let (prefix, line) = match kind {
+                Kind::Equal => (' ', a[i]),
+                Kind::Delete => ('-', a[i]),
+                Kind::Insert => ('+', b[j]),
+            };
+            match line.strip_suffix('\n') {
+                Some(text) => out.push_str(&format!("{}{}\n", prefix, text.trim_end_matches('\r'))),
+                None => out.push_str(&format!(
+                    "{}{}\n\\ No newline at end of file\n",
+                    prefix, line
+                )),
+            }
+        }
+    }
+    out
+}
+
+/// Most line edits [`shortest_edit`] looks for before giving up, which bounds its time and
+/// memory on files that were mostly rewritten.
+const MAX_EDITS: usize = 1000;
+
+/// Line operations turning `a` into `b`, each with the positions in `a` and `b` it applies at.
+fn diff_ops(a: &[&str], b: &[&str]) -> Vec<(Kind, usize, usize)> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let (n, m) = (a_mid.len(), b_mid.len());
+    let mut ops: Vec<(Kind, usize, usize)> = (0..prefix).map(|i| (Kind::Equal, i, i)).collect();
+    // Too different to be worth aligning: replace the whole region
+    let kinds = shortest_edit(a_mid, b_mid).unwrap_or_else(|| {
+        let mut kinds = vec![Kind::Delete; n];
+        kinds.extend(std::iter::repeat_n(Kind::Insert, m));
+        kinds
+    });
+    let (mut i, mut j) = (prefix, prefix);
+    for kind in kinds {
+        ops.push((kind, i, j));
+        if kind != Kind::Insert {
+            i += 1;
+        }
+        if kind != Kind::Delete {
+            j += 1;
+        }
+    }
+    ops.extend((0..suffix).map(|k| (Kind::Equal, prefix + n + k, prefix + m + k)));
+    ops
+}
+
+/// The operations of a shortest edit script from `a` to `b`, found with Myers' algorithm,
+/// or `None` when it takes more than [`MAX_EDITS`] insertions and deletions.
+fn shortest_edit(a: &[&str], b: &[&str]) -> Option<Vec<Kind>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let limit = (a.len() + b.len()).min(MAX_EDITS) as isize;
+    // Furthest `x` reached on each diagonal `k = x - y`, offset to index from 0
+    let offset = limit + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // The diagonals -d..=d of `v` before each step `d`, to walk the path back
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let at = |k: isize| (k + offset) as usize;
+
+    let mut end = None;
+    'search: for d in 0..=limit {
+        trace.push(v[at(-d)..=at(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                end = Some(d);
+                break 'search;
+            }
+        }
+    }
+
+    let mut kinds = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (0..=end?).rev() {
+        let snapshot = &trace[d as usize];
+        let before = |k: isize| snapshot[(k + d) as usize];
+        let k = x - y;
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let prev_k = if k == -d || (k != d && before(k - 1) < before(k + 1)) {
+                k + 1
+            } else {
+                k - 1
+            };
+            (before(prev_k), before(prev_k) - prev_k)
+        };
+        while x > prev_x && y > prev_y {
+            kinds.push(Kind::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            kinds.push(if x == prev_x {
+                Kind::Insert
+            } else {
+                Kind::Delete
+            });
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    kinds.reverse();
+    Some(kinds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rebuild `b` from `a` with the operations, checking each one applies where it says.
+    fn replay(a: &[&str], b: &[&str]) -> Vec<String> {
+        let mut out = Vec::new();
+        for (kind, i, j) in diff_ops(a, b) {
+            match kind {
+                Kind::Equal => {
+                    assert_eq!(a[i], b[j]);
+                    out.push(a[i].to_string());
+                }
+                Kind::Delete => {}
+                Kind::Insert => out.push(b[j].to_string()),
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn shows_changes_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\n";
+        let new = "a\nb\nc\nD\ne\nf\ng\nh\n";
+        assert_eq!(
+            unified_diff(old, new, "hyprland.conf", 1),
+            "--- hyprland.conf\n+++ hyprland.conf\n\
+             @@ -3,3 +3,3 @@\n c\n-d\n+D\n e\n\
+             @@ -7,1 +7,2 @@\n g\n+h\n"
+        );
+        assert_eq!(unified_diff(old, old, "hyprland.conf", 3), "");
+    }
+
+    #[test]
+    fn marks_a_missing_final_newline() {
+        assert_eq!(
+            unified_diff("a\nb", "a\nb\n", "f", 0),
+            "--- f\n+++ f\n@@ -2,1 +2,1 @@\n-b\n\\ No newline at end of file\n+b\n"
+        );
+    }
+
+    #[test]
+    fn finds_a_shortest_edit() {
+        let a: Vec<&str> = "abcabba".split("").filter(|s| !s.is_empty()).collect();
+        let b: Vec<&str> = "cbabac".split("").filter(|s| !s.is_empty()).collect();
+        assert_eq!(replay(&a, &b), b);
+        let edits = diff_ops(&a, &b)
+            .iter()
+            .filter(|(kind, _, _)| *kind != Kind::Equal)
+            .count();
+        assert_eq!(edits, 5);
+    }
+
+    #[test]
+    fn handles_large_files_changed_at_both_ends() {
+        let a: Vec<String> = (0..50_000)
+            .map(|i| format!("bind = SUPER, {}\n", i))
+            .collect();
+        let mut b = a.clone();
+        b[0] = "# first\n".to_string();
+        b[49_999] = "# last\n".to_string();
+        b.insert(25_000, "# middle\n".to_string());
+        let a: Vec<&str> = a.iter().map(String::as_str).collect();
+        let b: Vec<&str> = b.iter().map(String::as_str).collect();
+        assert_eq!(replay(&a, &b), b);
+
+        // Unrelated content falls back to replacing the region
+        let c: Vec<String> = (0..5_000).map(|i| format!("other {}\n", i)).collect();
+        let c: Vec<&str> = c.iter().map(String::as_str).collect();
+        assert_eq!(replay(&a[..5_000], &c), c);
+    }
+}
@@ -0,0 +1,245 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::ipc::Monitor;
+
+/// Where the kernel lists DRM connectors.
+pub const DEFAULT_ROOT: &str = "/sys/class/drm";
+/// Vendor names for the three letter PNP IDs in EDID, as shipped by hwdata.
+const PNP_IDS: &str = "/usr/share/hwdata/pnp.ids";
+
+/// Connected outputs as the kernel reports them under `root`, for when no compositor
+/// answers. Names, descriptions and modes follow what Hyprland would report; positions,
+/// scales and refresh rates are left at their defaults.
+pub fn monitors(root: &Path) -> Vec<Monitor> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    // `card1-eDP-1`, skipping the `card1` device itself
+    let mut connectors: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            let (card, name) = dir_name.split_once('-')?;
+            card.starts_with("card")
+                .then(|| (name.to_string(), entry.path()))
+        })
+        .collect();
+    connectors.sort();
+
+    let mut monitors = Vec::new();
+    for (name, dir) in connectors {
+        let status = fs::read_to_string(dir.join("status")).unwrap_or_default();
+        if status.trim() != "connected" {
+            continue;
+        }
+        let mut modes: Vec<String> = Vec::new();
+        for mode in fs::read_to_string(dir.join("modes"))
+            .unwrap_or_default()
+            .lines()
+        {
+            let mode = mode.trim().to_string();
+            // Interlaced modes (`1920x1080i`) can't be set with a `monitor=` rule
+            if !mode.is_empty() && !mode.ends_with('i') && !modes.contains(&mode) {
+                modes.push(mode);
+            }
+        }
+        // The preferred mode comes first
+        let (width, height) = modes
+            .first()
+            .and_then(|mode| mode.split_once('x'))
+            .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+            .unwrap_or((0, 0));
+        let mut monitor = Monitor {
+            id: monitors.len() as i64,
+            name,
+            width,
+            height,
+            scale: 1.0,
+            available_modes: modes,
+            ..Default::default()
+        };
+        if let Ok(edid) = fs::read(dir.join("edid"))
+            && let Some(info) = Edid::parse(&edid)
+        {
+            monitor.make = vendor_name(Path::new(PNP_IDS), &info.vendor);
+            monitor.model = info.model;
+            monitor.serial = info.serial;
+            monitor.description = [&monitor.make, &monitor.model, &monitor.serial]
+                .iter()
+                .filter(|s| !s.is_empty())
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
+        monitors.push(monitor);
+    }
+    monitors
+}
+
+/// The parts of an EDID base block that make up a monitor description.
+struct Edid {
+    vendor: String,
+    model: String,
+    serial: String,
+}
+
+impl Edid {
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 128 || data[..8] != [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00] {
+            return None;
+        }
+        let id = u16::from_be_bytes([data[8], data[9]]);
+        let vendor: String = [10, 5, 0]
+            .iter()
+            .map(|shift| (b'@' + ((id >> shift) & 0x1f) as u8) as char)
+            .collect();
+        let product = u16::from_le_bytes([data[10], data[11]]);
+        let serial_number = u32::from_le_bytes([data[12], data[13], data[14], data[15]]);
+
+        let mut name = None;
+        let mut serial = None;
+        // Display descriptors start with three zero bytes, then their tag
+        for descriptor in data[54..126].chunks(18) {
+            if descriptor[..3] != [0, 0, 0] {
+                continue;
+            }
+            let text = || descriptor_text(&descriptor[5..]);
+            match descriptor[3] {
+                0xfc => name = Some(text()),
+                0xff => serial = Some(text()),
+                _ => {}
+            }
+        }
+        // Without text descriptors, the codes are shown as hex like libdisplay-info does
+        Some(Edid {
+            vendor,
+            model: name.unwrap_or_else(|| format!("0x{:04X}", product)),
+            serial: serial.unwrap_or_else(|| {
+                if serial_number == 0 {
+                    String::new()
+                } else {
+                    format!("0x{:08X}", serial_number)
+                }
+            }),
+        })
+    }
+}
+
+/// Descriptor text ends at a newline and is padded with spaces.
+fn descriptor_text(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .position(|b| *b == b'\n')
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+/// The vendor name for a PNP ID from the `pnp_ids` list, or the ID itself when hwdata
+/// isn't installed.
+fn vendor_name(pnp_ids: &Path, id: &str) -> String {
+    fs::read_to_string(pnp_ids)
+        .ok()
+        .and_then(|content| {
+            content.lines().find_map(|line| {
+                let (code, name) = line.split_once('\t')?;
+                (code == id).then(|| name.trim().to_string())
+            })
+        })
+        .unwrap_or_else(|| id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// An EDID base block for a Dell monitor with name and serial descriptors.
+    fn edid(descriptors: bool) -> Vec<u8> {
+        let mut data = vec![0u8; 128];
+        data[..8].copy_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+        // "DEL" as three 5-bit letters
+        data[8..10].copy_from_slice(&[0x10, 0xac]);
+        data[10..12].copy_from_slice(&0xa0c4u16.to_le_bytes());
+        data[12..16].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+        if descriptors {
+            data[54..59].copy_from_slice(&[0, 0, 0, 0xfc, 0]);
+            data[59..72].copy_from_slice(b"DELL U2720Q\n ");
+            data[72..77].copy_from_slice(&[0, 0, 0, 0xff, 0]);
+            data[77..90].copy_from_slice(b"7X2KQ83\n     ");
+        }
+        data
+    }
+
+    /// A sysfs-like tree with a connected `eDP-1` and a disconnected `HDMI-A-1`.
+    fn drm_root() -> PathBuf {
+        let root = env::temp_dir().join(format!("hyprgui-drm-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let edp = root.join("card0-eDP-1");
+        let hdmi = root.join("card0-HDMI-A-1");
+        fs::create_dir_all(root.join("card0")).unwrap();
+        fs::create_dir_all(&edp).unwrap();
+        fs::create_dir_all(&hdmi).unwrap();
+        fs::write(edp.join("status"), "connected\n").unwrap();
+        fs::write(
+            edp.join("modes"),
+            "3840x2160\n1920x1080i\n2560x1440\n3840x2160\n",
+        )
+        .unwrap();
+        fs::write(edp.join("edid"), edid(true)).unwrap();
+        fs::write(hdmi.join("status"), "disconnected\n").unwrap();
+        fs::write(hdmi.join("modes"), "").unwrap();
+        root
+    }
+
+    #[test]
+    fn lists_connected_outputs() {
+        let root = drm_root();
+        let monitors = monitors(&root);
+        assert_eq!(monitors.len(), 1);
+        let monitor = &monitors[0];
+        assert_eq!(monitor.name, "eDP-1");
+        assert_eq!((monitor.width, monitor.height), (3840, 2160));
+        assert_eq!(monitor.available_modes, ["3840x2160", "2560x1440"]);
+        assert_eq!(monitor.make, vendor_name(Path::new(PNP_IDS), "DEL"));
+        assert_eq!(monitor.model, "DELL U2720Q");
+        assert_eq!(monitor.serial, "7X2KQ83");
+        assert_eq!(
+            monitor.description,
+            format!("{} DELL U2720Q 7X2KQ83", monitor.make)
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn parses_edid_descriptors() {
+        let info = Edid::parse(&edid(true)).unwrap();
+        assert_eq!(info.vendor, "DEL");
+        assert_eq!(info.model, "DELL U2720Q");
+        assert_eq!(info.serial, "7X2KQ83");
+    }
+
+    #[test]
+    fn falls_back_to_edid_codes() {
+        let info = Edid::parse(&edid(false)).unwrap();
+        assert_eq!(info.vendor, "DEL");
+        assert_eq!(info.model, "0xA0C4");
+        assert_eq!(info.serial, "0x12345678");
+        assert!(Edid::parse(&edid(true)[..127]).is_none());
+        assert!(Edid::parse(&[0; 128]).is_none());
+    }
+
+    #[test]
+    fn looks_up_pnp_vendors() {
+        let path = env::temp_dir().join(format!("hyprgui-pnp-{}.ids", std::process::id()));
+        fs::write(
+            &path,
+            "DEC\tDigital Equipment Corporation\nDEL\tDell Inc.\n",
+        )
+        .unwrap();
+        assert_eq!(vendor_name(&path, "DEL"), "Dell Inc.");
+        assert_eq!(vendor_name(&path, "BOE"), "BOE");
+        assert_eq!(vendor_name(&path.with_extension("missing"), "DEL"), "DEL");
+        fs::remove_file(path).unwrap();
+    }
+}
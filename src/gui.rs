@@ -1,17 +1,53 @@
 use gtk::{
-    Application, ApplicationWindow, Box, Button, ColorButton, DropDown, Entry, HeaderBar, Image,
-    Label, MessageDialog, Orientation, Popover, ScrolledWindow, SearchEntry, SpinButton, Stack,
-    StackSidebar, StringList, Switch, Widget, gdk, glib, prelude::*,
+    Application, ApplicationWindow, Box, Button, ColorButton, DropDown, HeaderBar, Image, Label,
+    MessageDialog, Orientation, Popover, ScrolledWindow, SearchEntry, Stack, StackSidebar,
+    StringList, Widget, gdk, gio, glib, prelude::*,
 };
 
 use hyprparser::HyprlandConfig;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-
+use std::sync::Arc;
+
+use crate::config_errors::ConfigError;
+use crate::config_lines;
+use crate::defaults::Defaults;
+use crate::history::EditHistory;
+use crate::ipc::{self, SocketClient};
+use crate::live::{self, LivePreview};
 use crate::modules::ConfigWidget;
+use crate::profiles;
+use crate::runtime;
+use crate::settings::Settings;
+use crate::widgets::{
+    CHANGES_ACTION, UNSET, format_rgba, set_conflict, set_modified, set_widget_value, widget_value,
+};
+
+/// Window action fired by the file monitors when a watched config file changes.
+pub const EXTERNAL_CHANGE_ACTION: &str = "win.external-change";
+
+/// Sidebar pages: display name and config category.
+const CATEGORIES: [(&str, &str); 16] = [
+    ("Monitors", "monitors"),
+    ("General", "general"),
+    ("Decoration", "decoration"),
+    ("Animations", "animations"),
+    ("Input", "input"),
+    ("Gestures", "gestures"),
+    ("Misc", "misc"),
+    ("Binds", "binds"),
+    ("Keybinds", "keybinds"),
+    ("Group", "group"),
+    ("Layouts", "layouts"),
+    ("XWayland", "xwayland"),
+    ("OpenGL", "opengl"),
+    ("Render", "render"),
+    ("Cursor", "cursor"),
+    ("Debug", "debug"),
+];
 
 pub fn add_dropdown_option(
     container: &Box,
@@ -149,6 +185,8 @@ pub fn get_option_limits(name: &str, description: &str) -> (f64, f64, f64) {
 
 pub struct ConfigGUI {
     pub window: ApplicationWindow,
+    /// The main hyprland.conf being edited.
+    pub config_path: PathBuf,
     pub config_widgets: HashMap<String, ConfigWidget>,
     pub save_button: Button,
     pub search_entry: SearchEntry,
@@ -159,10 +197,26 @@ pub struct ConfigGUI {
     load_config_button: Button,
     save_config_button: Button,
     pub gear_menu: Rc<RefCell<Popover>>,
+    target_file_dropdown: DropDown,
+    /// Picks the Hyprland instance that runtime queries and live preview talk to.
+    pub instance_dropdown: DropDown,
+    pub settings: Rc<RefCell<Settings>>,
+    history: Rc<RefCell<EditHistory>>,
+    defaults: Rc<Defaults>,
+    /// Option values as last loaded from disk, to spot external edits that conflict.
+    loaded_values: HashMap<(String, String), String>,
+    /// Watched config files and their content as last loaded or written by us.
+    watched_files: Vec<(PathBuf, String)>,
+    file_monitors: Vec<gio::FileMonitor>,
+    pub live: Rc<RefCell<LivePreview>>,
+    /// Client for the Hyprland instance picked in `instance_dropdown`, `None` when offline.
+    pub client: Option<Arc<SocketClient>>,
+    /// Options not set in any file, whose widgets show the running or default value.
+    placeholder_keys: Rc<RefCell<HashSet<(String, String)>>>,
 }
 
 impl ConfigGUI {
-    pub fn new(app: &Application) -> Self {
+    pub fn new(app: &Application, config_path: PathBuf) -> Self {
         let window = ApplicationWindow::builder()
             .application(app)
             .default_width(1000)
@@ -201,6 +255,19 @@ impl ConfigGUI {
         gear_menu_box.append(&load_config_button);
         gear_menu_box.append(&save_config_button);
 
+        let target_file_label = Label::new(Some("Write new options to:"));
+        target_file_label.set_halign(gtk::Align::Start);
+        target_file_label.set_margin_top(5);
+        let target_file_dropdown = DropDown::new(
+            Some(StringList::new(&["hyprland.conf"])),
+            None::<gtk::Expression>,
+        );
+        target_file_dropdown.set_tooltip_text(Some(
+            "File that receives options which are not defined in any config file yet.",
+        ));
+        gear_menu_box.append(&target_file_label);
+        gear_menu_box.append(&target_file_dropdown);
+
         gear_menu.borrow().set_child(Some(&gear_menu_box));
 
         let gear_menu_clone = gear_menu.clone();
@@ -236,6 +303,29 @@ impl ConfigGUI {
 
         header_bar.pack_start(&search_button);
 
+        let undo_button = Button::from_icon_name("edit-undo-symbolic");
+        undo_button.set_action_name(Some("win.undo"));
+        undo_button.set_tooltip_text(Some("Undo last edit (Ctrl+Z)"));
+        let redo_button = Button::from_icon_name("edit-redo-symbolic");
+        redo_button.set_action_name(Some("win.redo"));
+        redo_button.set_tooltip_text(Some("Redo edit (Ctrl+Shift+Z)"));
+        header_bar.pack_start(&undo_button);
+        header_bar.pack_start(&redo_button);
+
+        let live_button = gtk::ToggleButton::with_label("Live");
+        live_button.set_action_name(Some("win.live-preview"));
+        live_button.set_tooltip_text(Some(
+            "Apply edits to the running Hyprland right away, without saving",
+        ));
+        header_bar.pack_start(&live_button);
+
+        let instance_dropdown =
+            DropDown::new(Some(StringList::new(&["Offline"])), None::<gtk::Expression>);
+        instance_dropdown.set_tooltip_text(Some(
+            "Hyprland instance used for running values, monitors and live preview",
+        ));
+        header_bar.pack_start(&instance_dropdown);
+
         let save_button = Button::with_label("Save");
         header_bar.pack_end(&save_button);
 
@@ -258,6 +348,7 @@ impl ConfigGUI {
 
         ConfigGUI {
             window,
+            config_path,
             config_widgets,
             save_button,
             search_entry,
@@ -268,6 +359,17 @@ impl ConfigGUI {
             load_config_button,
             save_config_button,
             gear_menu,
+            target_file_dropdown,
+            instance_dropdown,
+            settings: Rc::new(RefCell::new(Settings::load())),
+            history: Rc::new(RefCell::new(EditHistory::default())),
+            defaults: Rc::new(Defaults::builtin()),
+            loaded_values: HashMap::new(),
+            watched_files: Vec::new(),
+            file_monitors: Vec::new(),
+            live: Rc::new(RefCell::new(LivePreview::default())),
+            client: ipc::default_target().client(),
+            placeholder_keys: Rc::new(RefCell::new(HashSet::new())),
         }
     }
 
@@ -351,13 +453,14 @@ impl ConfigGUI {
                             if let Some(widget) = self.config_widgets.get(&category)
                                 && let Some(option_widget) = widget.options.get(&name)
                             {
-                                self.set_widget_value(option_widget, &value);
+                                set_widget_value(option_widget, &value);
                                 self.changed_options
                                     .borrow_mut()
                                     .insert((category, name), value);
                             }
                         }
                     }
+                    self.changes_updated();
                     self.custom_info_popup(
                         "Config Loaded",
                         "HyprGUI configuration loaded successfully.",
@@ -416,34 +519,6 @@ impl ConfigGUI {
         }
     }
 
-    fn set_widget_value(&self, widget: &Widget, value: &str) {
-        if let Some(spin_button) = widget.downcast_ref::<SpinButton>() {
-            if let Ok(float_value) = value.parse::<f64>() {
-                spin_button.set_value(float_value);
-            }
-        } else if let Some(entry) = widget.downcast_ref::<Entry>() {
-            entry.set_text(value);
-        } else if let Some(switch) = widget.downcast_ref::<Switch>() {
-            switch.set_active(value == "true");
-        } else if let Some(color_button) = widget.downcast_ref::<ColorButton>() {
-            let dummy_config = HyprlandConfig::new();
-            if let Some((red, green, blue, alpha)) = dummy_config.parse_color(value) {
-                color_button.set_rgba(&gdk::RGBA::new(red, green, blue, alpha));
-            }
-        } else if let Some(dropdown) = widget.downcast_ref::<DropDown>() {
-            let model = dropdown.model().unwrap();
-            for i in 0..model.n_items() {
-                if let Some(item) = model.item(i)
-                    && let Some(string_object) = item.downcast_ref::<gtk::StringObject>()
-                    && string_object.string() == value
-                {
-                    dropdown.set_selected(i);
-                    break;
-                }
-            }
-        }
-    }
-
     pub fn custom_info_popup(&mut self, title: &str, text: &str, modal: bool) {
         let dialog = MessageDialog::builder()
             .message_type(gtk::MessageType::Info)
@@ -476,24 +551,11 @@ impl ConfigGUI {
         dialog.show();
     }
 
-    pub fn custom_error_popup_critical(&mut self, title: &str, text: &str, modal: bool) {
-        let dialog = MessageDialog::builder()
-            .message_type(gtk::MessageType::Error)
-            .buttons(gtk::ButtonsType::Ok)
-            .title(title)
-            .text(text)
-            .modal(modal)
-            .build();
-
-        dialog.connect_response(|_, _| {
-            std::process::exit(1);
-        });
-
-        dialog.show();
-    }
-
     pub fn load_config(&mut self, config: &HyprlandConfig) {
+        let visible_page = self.stack.visible_child_name();
         self.config_widgets.clear();
+        // Cleared first, since loading the widgets records their initial values
+        self.history.borrow_mut().clear();
         self.content_box.set_visible(true);
 
         while let Some(child) = self.stack.first_child() {
@@ -520,38 +582,461 @@ impl ConfigGUI {
             }
         });
 
-        let categories = [
-            ("Monitors", "monitors"),
-            ("General", "general"),
-            ("Decoration", "decoration"),
-            ("Animations", "animations"),
-            ("Input", "input"),
-            ("Gestures", "gestures"),
-            ("Misc", "misc"),
-            ("Binds", "binds"),
-            ("Group", "group"),
-            ("Layouts", "layouts"),
-            ("XWayland", "xwayland"),
-            ("OpenGL", "opengl"),
-            ("Render", "render"),
-            ("Cursor", "cursor"),
-            ("Debug", "debug"),
-        ];
-
-        for (display_name, category) in &categories {
+        for (display_name, category) in CATEGORIES {
             let widget = ConfigWidget::new(category);
             self.stack
                 .add_titled(&widget.scrolled_window, Some(category), display_name);
             self.config_widgets.insert(category.to_string(), widget);
         }
 
-        for (_, category) in &categories {
-            if let Some(widget) = self.config_widgets.get(*category) {
-                widget.load_config(config, category, self.changed_options.clone());
+        if let Some(page) = visible_page {
+            self.stack.set_visible_child_name(&page);
+        }
+
+        self.loaded_values.clear();
+        let drm_root = self.settings.borrow().drm_root.clone();
+        for (_, category) in CATEGORIES {
+            if let Some(widget) = self.config_widgets.get(category) {
+                widget.load_config(
+                    config,
+                    category,
+                    self.changed_options.clone(),
+                    self.history.clone(),
+                    &self.defaults,
+                    &self.config_path,
+                    &drm_root,
+                    self.client.as_ref(),
+                );
+                for (name, option_widget) in &widget.options {
+                    let (section, key) = config_lines::option_path(category, name);
+                    let location = config_lines::locate_option(config, &section, &key);
+                    if let Some(location) = &location
+                        && let Some(row) = option_widget.parent()
+                    {
+                        row.set_tooltip_text(Some(&format!(
+                            "Defined in {}",
+                            config_lines::source_label(config, location.source)
+                        )));
+                    }
+                    self.loaded_values.insert(
+                        (category.to_string(), name.to_string()),
+                        location.map(|l| l.value).unwrap_or_default(),
+                    );
+                }
             }
         }
 
+        self.apply_runtime_values();
+
+        let selected = self.target_file_dropdown.selected();
+        let files: Vec<String> = std::iter::once(None)
+            .chain((0..config.sourced_paths.len()).map(Some))
+            .map(|source| config_lines::source_label(config, source))
+            .collect();
+        let files_refs: Vec<&str> = files.iter().map(|s| s.as_str()).collect();
+        self.target_file_dropdown
+            .set_model(Some(&StringList::new(&files_refs)));
+        if (selected as usize) < files.len() {
+            self.target_file_dropdown.set_selected(selected);
+        }
+
         self.changed_options.borrow_mut().clear();
+        self.live.borrow_mut().forget();
+        self.refresh_modified();
+    }
+
+    /// Reload after the files changed on disk, re-applying pending option and table edits
+    /// on top. Edits to options, rules and binds that changed on disk too are kept but
+    /// flagged. Returns the number of conflicts.
+    pub fn reload_keeping_changes(&mut self, config: &HyprlandConfig) -> usize {
+        let pending: Vec<((String, String), String)> = self
+            .changed_options
+            .borrow()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let table_edits = self
+            .config_widgets
+            .get("monitors")
+            .map(|widget| widget.rule_tables.pending_edits());
+        let bind_edits = self
+            .config_widgets
+            .get("keybinds")
+            .map(|widget| widget.bind_table.pending_edits());
+        let previous = std::mem::take(&mut self.loaded_values);
+        self.load_config(config);
+
+        let mut conflicts = 0;
+        for (key, value) in pending {
+            let Some(widget) = self
+                .config_widgets
+                .get(&key.0)
+                .and_then(|widget| widget.options.get(&key.1))
+            else {
+                // Table edits are replayed below
+                continue;
+            };
+
+            if value != UNSET {
+                self.history.borrow_mut().set_replaying(true);
+                set_widget_value(widget, &value);
+                self.history.borrow_mut().set_replaying(false);
+            }
+            let shown = widget_value(widget).unwrap_or_default();
+            {
+                let mut history = self.history.borrow_mut();
+                if value == UNSET {
+                    history.record_unset(key.clone(), &shown, None);
+                } else {
+                    history.record(key.clone(), &shown, None);
+                }
+            }
+
+            let on_disk = self.loaded_values.get(&key).cloned().unwrap_or_default();
+            if previous.get(&key).is_some_and(|before| *before != on_disk)
+                && let Some(row) = widget.parent()
+            {
+                conflicts += 1;
+                set_conflict(
+                    &row,
+                    &format!(
+                        "Also changed on disk to \"{}\". Saving keeps your value, undo restores it.",
+                        on_disk
+                    ),
+                );
+            }
+            self.changed_options.borrow_mut().insert(key, value);
+        }
+
+        // The monitor rows are filled in once the outputs are known, so their edits wait
+        if let Some(edits) = table_edits.filter(|edits| !edits.is_empty())
+            && let Some(widget) = self.config_widgets.get("monitors")
+        {
+            conflicts += edits.conflicts(config);
+            *widget.rule_tables.replay.borrow_mut() = Some(edits);
+        }
+        if let Some(edits) = bind_edits
+            && let Some(widget) = self.config_widgets.get("keybinds")
+        {
+            conflicts += widget.replay_binds(edits, &self.changed_options);
+        }
+        self.changes_updated();
+        conflicts
+    }
+
+    /// Watch the main config and its sourced files for edits made outside HyprGUI.
+    pub fn watch_files(&mut self, main_path: &Path, config: &HyprlandConfig) {
+        self.file_monitors.clear();
+        self.watched_files.clear();
+
+        let paths = std::iter::once(main_path.to_path_buf())
+            .chain(config.sourced_paths.iter().map(PathBuf::from));
+        for path in paths {
+            let path = fs::canonicalize(&path).unwrap_or(path);
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            let monitor = gio::File::for_path(&path)
+                .monitor_file(gio::FileMonitorFlags::NONE, None::<&gio::Cancellable>);
+            match monitor {
+                Ok(monitor) => {
+                    let window = self.window.downgrade();
+                    monitor.connect_changed(move |_, _, _, event| {
+                        if matches!(
+                            event,
+                            gio::FileMonitorEvent::ChangesDoneHint
+                                | gio::FileMonitorEvent::Created
+                                | gio::FileMonitorEvent::Deleted
+                        ) && let Some(window) = window.upgrade()
+                        {
+                            let _ =
+                                WidgetExt::activate_action(&window, EXTERNAL_CHANGE_ACTION, None);
+                        }
+                    });
+                    self.file_monitors.push(monitor);
+                }
+                Err(e) => eprintln!("Failed to watch {}: {}", path.display(), e),
+            }
+            self.watched_files.push((path, content));
+        }
+    }
+
+    /// Watched files whose content differs from what HyprGUI last loaded or wrote.
+    pub fn externally_changed_files(&self) -> Vec<PathBuf> {
+        self.watched_files
+            .iter()
+            .filter(|(path, content)| fs::read_to_string(path).unwrap_or_default() != *content)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Accept the current content of the watched files without reloading.
+    pub fn acknowledge_file_changes(&mut self) {
+        for (path, content) in &mut self.watched_files {
+            *content = fs::read_to_string(&*path).unwrap_or_default();
+        }
+    }
+
+    /// Compare the loaded options with the running compositor. Options missing from the
+    /// files show the running value (or the default) as a placeholder, and options whose
+    /// running value differs from the file get an indicator. The query runs off the main
+    /// thread and the pages are updated once it answers.
+    fn apply_runtime_values(&mut self) {
+        self.placeholder_keys.borrow_mut().clear();
+        let options: Vec<((String, String), Widget, String)> = self
+            .config_widgets
+            .iter()
+            .flat_map(|(category, widget)| {
+                widget
+                    .options
+                    .iter()
+                    .filter(|(_, option_widget)| widget_value(option_widget).is_some())
+                    .map(|(name, option_widget)| {
+                        let key = (category.clone(), name.clone());
+                        let file_value = self.loaded_values.get(&key).cloned().unwrap_or_default();
+                        (key, option_widget.clone(), file_value)
+                    })
+            })
+            .collect();
+        let keys: Vec<String> = options
+            .iter()
+            .map(|((category, name), _, _)| config_lines::full_key(category, name))
+            .collect();
+
+        let defaults = self.defaults.clone();
+        let history = self.history.clone();
+        let changed_options = self.changed_options.clone();
+        let placeholder_keys = self.placeholder_keys.clone();
+        let window = self.window.clone();
+        let client = self.client.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let running = runtime::query_options(client, keys.clone()).await;
+
+            for ((key, option_widget, file_value), full_key) in options.into_iter().zip(keys) {
+                // The pages were rebuilt while the query ran
+                if option_widget.root().is_none() {
+                    return;
+                }
+                // Edits made meanwhile, or re-applied after a reload, keep their value
+                if changed_options.borrow().contains_key(&key) {
+                    continue;
+                }
+                let running_value = running.get(&full_key);
+
+                if file_value.is_empty() {
+                    let Some(value) = running_value
+                        .map(|v| v.as_str())
+                        .or_else(|| defaults.get(&full_key))
+                        .filter(|v| !v.is_empty())
+                    else {
+                        continue;
+                    };
+                    if let Some(entry) = option_widget.downcast_ref::<gtk::Entry>() {
+                        entry.set_placeholder_text(Some(value));
+                    } else {
+                        history.borrow_mut().set_replaying(true);
+                        set_widget_value(&option_widget, value);
+                        history.borrow_mut().set_replaying(false);
+                        if let Some(shown) = widget_value(&option_widget) {
+                            history.borrow_mut().set_initial(key.clone(), shown);
+                        }
+                        placeholder_keys.borrow_mut().insert(key);
+                    }
+                } else if let Some(running_value) = running_value
+                    && !runtime::same_value(&file_value, running_value)
+                    && let Some(row) = option_widget.parent().and_downcast::<Box>()
+                {
+                    let icon = Image::from_icon_name("dialog-warning-symbolic");
+                    icon.set_tooltip_text(Some(&format!(
+                        "The running Hyprland uses \"{}\" instead, e.g. set with hyprctl keyword or by a plugin.",
+                        running_value
+                    )));
+                    icon.insert_after(&row, Some(&option_widget));
+                }
+            }
+            let _ = WidgetExt::activate_action(&window, CHANGES_ACTION, None);
+        });
+    }
+
+    /// Mark the option, rule and bind rows whose config line Hyprland reported an error for.
+    pub fn flag_config_errors(&self, config: &HyprlandConfig, errors: &[ConfigError]) {
+        let source_paths: Vec<(Option<usize>, PathBuf)> =
+            std::iter::once((None, self.config_path.clone()))
+                .chain(
+                    config
+                        .sourced_paths
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, path)| (Some(idx), PathBuf::from(path))),
+                )
+                .map(|(source, path)| (source, fs::canonicalize(&path).unwrap_or(path)))
+                .collect();
+
+        for error in errors {
+            let (Some(path), Some(line)) = (&error.path, error.line) else {
+                continue;
+            };
+            let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            let Some(source) = source_paths
+                .iter()
+                .find(|(_, p)| *p == path)
+                .map(|(source, _)| *source)
+            else {
+                continue;
+            };
+            let Some(lines) = config_lines::lines(config, source) else {
+                continue;
+            };
+            if let Some(row) = self.row_at(source, lines, line.saturating_sub(1)) {
+                row.add_css_class("config-error");
+                row.set_tooltip_text(Some(&error.message));
+                let icon = Image::from_icon_name("dialog-error-symbolic");
+                icon.set_tooltip_text(Some(&error.message));
+                if let Some(row) = row.downcast_ref::<Box>() {
+                    row.append(&icon);
+                }
+            }
+        }
+    }
+
+    /// The row of the option, monitor/workspace rule or bind defined on line `idx` of
+    /// `source`.
+    fn row_at(&self, source: Option<usize>, lines: &[String], idx: usize) -> Option<Widget> {
+        self.option_row_at(lines, idx).or_else(|| {
+            self.config_widgets.values().find_map(|widget| {
+                widget
+                    .rule_tables
+                    .row_at(source, lines, idx)
+                    .or_else(|| widget.bind_table.row_at(source, lines, idx))
+                    .map(|row| row.upcast())
+            })
+        })
+    }
+
+    /// The row of the option defined on line `idx` of a file.
+    fn option_row_at(&self, lines: &[String], idx: usize) -> Option<Widget> {
+        for (category, widget) in &self.config_widgets {
+            for (name, option_widget) in &widget.options {
+                let (section, key) = config_lines::option_path(category, name);
+                if config_lines::find_options(lines, &section, &key)
+                    .iter()
+                    .any(|(line, _)| *line == idx)
+                {
+                    return option_widget.parent();
+                }
+            }
+        }
+        None
+    }
+
+    /// Update everything that follows `changed_options` after it changed.
+    pub fn changes_updated(&self) {
+        self.refresh_modified();
+        self.sync_live_preview();
+    }
+
+    /// While live preview is on, push pending option values to the compositor and put
+    /// back the earlier values of options that are no longer edited.
+    pub fn sync_live_preview(&self) {
+        if !self.live.borrow().is_enabled() {
+            return;
+        }
+
+        let mut wanted = HashMap::new();
+        for ((category, name), value) in self.changed_options.borrow().iter() {
+            // Monitor and workspace tables aren't options
+            if !self
+                .config_widgets
+                .get(category)
+                .is_some_and(|widget| widget.options.contains_key(name))
+            {
+                continue;
+            }
+            let key = config_lines::full_key(category, name);
+            let value = if value == UNSET {
+                self.defaults.get(&key).unwrap_or_default().to_string()
+            } else {
+                value.clone()
+            };
+            if !value.is_empty() {
+                wanted.insert(key, value);
+            }
+        }
+
+        self.live.borrow_mut().sync(wanted);
+        live::schedule_flush(&self.live, self.client.clone());
+    }
+
+    /// Use the defaults of the chosen instance for pages loaded from now on.
+    pub fn set_defaults(&mut self, defaults: Defaults) {
+        self.defaults = Rc::new(defaults);
+    }
+
+    /// Put back the values the compositor had before live preview pushed any.
+    pub fn revert_live_preview(&self) {
+        self.live.borrow_mut().revert();
+        live::schedule_flush(&self.live, self.client.clone());
+    }
+
+    /// Highlight rows with pending changes and show how many each sidebar page has.
+    fn refresh_modified(&self) {
+        let changes = self.changed_options.borrow();
+        for (display_name, category) in CATEGORIES {
+            let Some(widget) = self.config_widgets.get(category) else {
+                continue;
+            };
+            for (name, option_widget) in &widget.options {
+                let key = (category.to_string(), name.to_string());
+                if let Some(row) = option_widget.parent() {
+                    set_modified(&row, changes.contains_key(&key));
+                }
+                // Placeholder values fade out until edited
+                if self.placeholder_keys.borrow().contains(&key) && !changes.contains_key(&key) {
+                    option_widget.add_css_class("runtime-value");
+                } else {
+                    option_widget.remove_css_class("runtime-value");
+                }
+            }
+
+            let count = changes.keys().filter(|(c, _)| c == category).count();
+            let title = if count == 0 {
+                display_name.to_string()
+            } else {
+                format!("{} ({})", display_name, count)
+            };
+            self.stack.page(&widget.scrolled_window).set_title(&title);
+        }
+    }
+
+    /// Step back one option edit without touching any file.
+    pub fn undo(&self) {
+        let edit = self.history.borrow_mut().pop_undo();
+        if let Some(edit) = edit {
+            self.replay_edit(&edit.key, &edit.before, edit.changed_before);
+        }
+    }
+
+    pub fn redo(&self) {
+        let edit = self.history.borrow_mut().pop_redo();
+        if let Some(edit) = edit {
+            self.replay_edit(&edit.key, &edit.after, edit.changed_after);
+        }
+    }
+
+    fn replay_edit(&self, key: &(String, String), value: &str, changed: Option<String>) {
+        if let Some(widget) = self.config_widgets.get(&key.0)
+            && let Some(option_widget) = widget.options.get(&key.1)
+        {
+            self.history.borrow_mut().set_replaying(true);
+            set_widget_value(option_widget, value);
+            self.history.borrow_mut().set_replaying(false);
+        }
+        {
+            let mut changes = self.changed_options.borrow_mut();
+            match changed {
+                Some(value) => changes.insert(key.clone(), value),
+                None => changes.remove(key),
+            };
+        }
+        self.changes_updated();
     }
 
     pub fn get_changes(&self) -> Rc<RefCell<HashMap<(String, String), String>>> {
@@ -560,46 +1045,57 @@ impl ConfigGUI {
 
     pub fn apply_changes(&self, config: &mut HyprlandConfig) {
         let changes = self.changed_options.borrow();
+        let target = match self.target_file_dropdown.selected() {
+            0 | gtk::INVALID_LIST_POSITION => None,
+            idx => Some(idx as usize - 1).filter(|idx| *idx < config.sourced_content.len()),
+        };
         for (category, widget) in &self.config_widgets {
             for (name, widget) in &widget.options {
                 if let Some(value) = changes.get(&(category.to_string(), name.to_string())) {
+                    let (section, key) = config_lines::option_path(category, name);
+                    if value == UNSET {
+                        // Remove every definition, so a later one can't take over
+                        while let Some(location) =
+                            config_lines::locate_option(config, &section, &key)
+                            && let Some(lines) = config_lines::lines_mut(config, location.source)
+                            && config_lines::remove_option(lines, &section, &key)
+                        {
+                        }
+                        continue;
+                    }
+
                     let formatted_value =
                         if let Some(color_button) = widget.downcast_ref::<ColorButton>() {
-                            let rgba = color_button.rgba();
-                            format!(
-                                "rgba({:02X}{:02X}{:02X}{:02X})",
-                                (rgba.red() * 255.0) as u8,
-                                (rgba.green() * 255.0) as u8,
-                                (rgba.blue() * 255.0) as u8,
-                                (rgba.alpha() * 255.0) as u8
-                            )
+                            format_rgba(&color_button.rgba())
                         } else {
                             value.clone()
                         };
 
                     if !formatted_value.is_empty() {
-                        if category == "layouts" {
-                            let parts: Vec<&str> = name.split(':').collect();
-                            if parts.len() == 2 {
-                                config.add_entry(
-                                    parts[0],
-                                    &format!("{} = {}", parts[1], formatted_value),
-                                );
-                            }
-                        } else if name.contains(':') {
-                            let parts: Vec<&str> = name.split(':').collect();
-                            if parts.len() == 2 {
-                                config.add_entry(
-                                    &format!("{}.{}", category, parts[0]),
-                                    &format!("{} = {}", parts[1], formatted_value),
-                                );
-                            }
-                        } else {
-                            config.add_entry(category, &format!("{} = {}", name, formatted_value));
+                        // Edit the file that defines the key; new keys go to the chosen target file
+                        let source = config_lines::locate_option(config, &section, &key)
+                            .map(|location| location.source)
+                            .unwrap_or(target);
+                        if let Some(lines) = config_lines::lines_mut(config, source) {
+                            config_lines::set_option(lines, &section, &key, &formatted_value);
                         }
                     }
                 }
             }
         }
+
+        // Monitor/workspace and bind tables are rewritten last, since they locate their
+        // lines by text
+        let profile_path = profiles::profile_path(&self.config_path);
+        let new_rules = profiles::active_profile(&profile_path)
+            .and_then(|_| profiles::source_index(config, &profile_path));
+        for widget in self.config_widgets.values() {
+            if widget.rule_tables.is_modified() {
+                widget.rule_tables.apply(config, new_rules);
+            }
+            if widget.bind_table.is_modified() {
+                widget.bind_table.apply(config);
+            }
+        }
     }
 }
@@ -0,0 +1,174 @@
+use gtk::glib;
+use std::collections::HashMap;
+
+use crate::widgets::UNSET;
+
+/// Edits to the same option closer together than this are merged into one undo step,
+/// so dragging a slider or typing a word is undone at once.
+const MERGE_WINDOW_US: i64 = 800_000;
+
+/// One option edit, with enough state to restore both sides.
+#[derive(Clone, Debug)]
+pub struct Edit {
+    pub key: (String, String),
+    /// Widget value before the edit.
+    pub before: String,
+    /// Widget value after the edit.
+    pub after: String,
+    /// `changed_options` entry for the key before the edit.
+    pub changed_before: Option<String>,
+    /// `changed_options` entry for the key after the edit.
+    pub changed_after: Option<String>,
+    time: i64,
+}
+
+/// Undo/redo stacks for the option widgets of the current session.
+#[derive(Default)]
+pub struct EditHistory {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    values: HashMap<(String, String), String>,
+    replaying: bool,
+}
+
+impl EditHistory {
+    /// Remember the value an option had when it was loaded.
+    pub fn set_initial(&mut self, key: (String, String), value: String) {
+        self.values.insert(key, value);
+    }
+
+    /// The value an option's widget showed when last loaded or recorded.
+    pub fn value(&self, key: &(String, String)) -> Option<&str> {
+        self.values.get(key).map(|s| s.as_str())
+    }
+
+    /// Record a widget change; `changed_before` is the pending value before it.
+    pub fn record(&mut self, key: (String, String), value: &str, changed_before: Option<String>) {
+        let before = self.values.insert(key.clone(), value.to_string());
+        let before = before.unwrap_or_default();
+        if before == value {
+            return;
+        }
+        self.redo.clear();
+
+        let now = glib::monotonic_time();
+        if let Some(last) = self.undo.last_mut()
+            && last.key == key
+            && now - last.time < MERGE_WINDOW_US
+        {
+            last.after = value.to_string();
+            last.changed_after = Some(value.to_string());
+            last.time = now;
+            return;
+        }
+        self.undo.push(Edit {
+            key,
+            before,
+            after: value.to_string(),
+            changed_before,
+            changed_after: Some(value.to_string()),
+            time: now,
+        });
+    }
+
+    /// Record a reset of an option to its default as a single step of its own.
+    pub fn record_unset(
+        &mut self,
+        key: (String, String),
+        value: &str,
+        changed_before: Option<String>,
+    ) {
+        let before = self.values.insert(key.clone(), value.to_string());
+        self.redo.clear();
+        self.undo.push(Edit {
+            key,
+            before: before.unwrap_or_default(),
+            after: value.to_string(),
+            changed_before,
+            changed_after: Some(UNSET.to_string()),
+            time: i64::MIN / 2,
+        });
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop()?;
+        self.values.insert(edit.key.clone(), edit.before.clone());
+        self.redo.push(edit.clone());
+        Some(edit)
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Edit> {
+        let mut edit = self.redo.pop()?;
+        self.values.insert(edit.key.clone(), edit.after.clone());
+        // Never merge a redone edit with the next one
+        edit.time = i64::MIN / 2;
+        self.undo.push(edit.clone());
+        Some(edit)
+    }
+
+    /// Whether widget signals are being caused by an undo/redo rather than the user.
+    pub fn is_replaying(&self) -> bool {
+        self.replaying
+    }
+
+    pub fn set_replaying(&mut self, replaying: bool) {
+        self.replaying = replaying;
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.values.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> (String, String) {
+        ("general".to_string(), name.to_string())
+    }
+
+    #[test]
+    fn merges_quick_edits_to_one_option() {
+        let mut history = EditHistory::default();
+        history.set_initial(key("gaps_in"), "2".to_string());
+        history.record(key("gaps_in"), "3", None);
+        history.record(key("gaps_in"), "4", Some("3".to_string()));
+
+        let edit = history.pop_undo().unwrap();
+        assert_eq!((edit.before.as_str(), edit.after.as_str()), ("2", "4"));
+        assert_eq!(edit.changed_before, None);
+        assert!(history.pop_undo().is_none());
+        assert_eq!(history.value(&key("gaps_in")), Some("2"));
+    }
+
+    #[test]
+    fn keeps_edits_apart_outside_the_window() {
+        let mut history = EditHistory::default();
+        history.set_initial(key("gaps_in"), "2".to_string());
+        history.record(key("gaps_in"), "3", None);
+        history.undo.last_mut().unwrap().time -= MERGE_WINDOW_US;
+        history.record(key("gaps_in"), "4", Some("3".to_string()));
+        history.record(key("gaps_out"), "8", None);
+
+        assert_eq!(history.pop_undo().unwrap().key, key("gaps_out"));
+        assert_eq!(history.pop_undo().unwrap().before, "3");
+        assert_eq!(history.pop_undo().unwrap().before, "2");
+    }
+
+    #[test]
+    fn never_merges_unsets_or_redone_edits() {
+        let mut history = EditHistory::default();
+        history.record(key("gaps_in"), "3", None);
+        history.record_unset(key("gaps_in"), "5", Some("3".to_string()));
+        history.record(key("gaps_in"), "6", Some(UNSET.to_string()));
+        assert_eq!(history.pop_undo().unwrap().after, "6");
+
+        history.pop_redo();
+        history.record(key("gaps_in"), "7", Some("6".to_string()));
+        assert_eq!(history.pop_undo().unwrap().before, "6");
+        assert_eq!(history.pop_undo().unwrap().after, "6");
+    }
+}
@@ -0,0 +1,438 @@
+use gtk::{gio, glib, prelude::*};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::env;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a request may take before the compositor is considered unresponsive.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Reply of `j/monitors`.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Monitor {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub make: String,
+    pub model: String,
+    pub serial: String,
+    pub width: i32,
+    pub height: i32,
+    pub refresh_rate: f64,
+    pub x: i32,
+    pub y: i32,
+    pub scale: f64,
+    pub transform: i32,
+    pub focused: bool,
+    pub dpms_status: bool,
+    pub vrr: bool,
+    pub disabled: bool,
+    /// Pixel format, e.g. `XRGB2101010` while running at 10 bit.
+    pub current_format: String,
+    /// Name of the output this one mirrors, or `none`.
+    pub mirror_of: String,
+    /// `cm` mode of the output, reported by newer Hyprland versions.
+    pub color_management_preset: String,
+    pub sdr_brightness: f64,
+    pub sdr_saturation: f64,
+    pub available_modes: Vec<String>,
+}
+
+/// Reply of `j/getoption <key>`; exactly one of the value fields is set.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct OptionValue {
+    pub option: String,
+    pub int: Option<i64>,
+    pub float: Option<f64>,
+    pub str: Option<String>,
+    pub custom: Option<String>,
+    pub vec2: Option<(f64, f64)>,
+    pub set: bool,
+}
+
+impl OptionValue {
+    /// The value written the way a config file would.
+    pub fn to_config_string(&self) -> Option<String> {
+        if let Some(n) = self.int {
+            // Colors are reported as plain ARGB integers
+            return Some(if self.option.contains("col.") {
+                format!("0x{:08x}", n as u32)
+            } else {
+                n.to_string()
+            });
+        }
+        if let Some(f) = self.float {
+            return Some(f.to_string());
+        }
+        if let Some((x, y)) = self.vec2 {
+            return Some(format!("{} {}", x, y));
+        }
+        self.str
+            .as_ref()
+            .or(self.custom.as_ref())
+            .map(|s| s.trim().to_string())
+    }
+}
+
+/// Requests HyprGUI makes to the compositor. Implemented over Hyprland's socket by
+/// [`SocketClient`]; anything answering the same text protocol can stand in for it.
+pub trait HyprlandIpc: Send + Sync {
+    /// Send one raw request, e.g. `j/monitors`, and return the raw reply.
+    fn request(&self, command: &str) -> io::Result<String>;
+
+    fn request_json<T: DeserializeOwned>(&self, command: &str) -> io::Result<T>
+    where
+        Self: Sized,
+    {
+        let reply = self.request(command)?;
+        serde_json::from_str(&reply).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", e, reply.trim()),
+            )
+        })
+    }
+
+    /// Send several commands in one `[[BATCH]]` request.
+    fn batch(&self, commands: &[String]) -> io::Result<String> {
+        self.request(&format!("[[BATCH]]{}", commands.join(";")))
+    }
+}
+
+/// Typed requests, available on every [`HyprlandIpc`].
+pub trait HyprlandRequests: HyprlandIpc + Sized {
+    fn monitors(&self) -> io::Result<Vec<Monitor>> {
+        self.request_json("j/monitors all")
+    }
+
+    fn descriptions(&self) -> io::Result<Value> {
+        self.request_json("j/descriptions")
+    }
+
+    /// Query many options in one request. Options the compositor doesn't know are left out.
+    fn getoptions(&self, keys: &[String]) -> io::Result<Vec<OptionValue>> {
+        let commands: Vec<String> = keys.iter().map(|k| format!("j/getoption {}", k)).collect();
+        let reply = self.batch(&commands)?;
+        Ok(parse_concatenated(&reply))
+    }
+
+    /// Set options at runtime, like `hyprctl keyword`. Returns the replies that aren't `ok`.
+    fn keywords(&self, values: &[(String, String)]) -> io::Result<Vec<String>> {
+        let commands: Vec<String> = values
+            .iter()
+            .map(|(key, value)| format!("/keyword {} {}", key, value))
+            .collect();
+        let reply = self.batch(&commands)?;
+        Ok(reply
+            .lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty() && l != "ok")
+            .collect())
+    }
+
+    fn reload(&self) -> io::Result<()> {
+        match self.request("/reload")?.trim() {
+            "ok" => Ok(()),
+            reply => Err(io::Error::other(reply.to_string())),
+        }
+    }
+
+    fn config_errors(&self) -> io::Result<Vec<String>> {
+        self.request_json("j/configerrors")
+    }
+}
+
+impl<T: HyprlandIpc> HyprlandRequests for T {}
+
+/// Client for the socket of one Hyprland instance.
+#[derive(Clone, Debug)]
+pub struct SocketClient {
+    /// `HYPRLAND_INSTANCE_SIGNATURE` of the instance.
+    pub signature: String,
+    pub path: PathBuf,
+}
+
+impl SocketClient {
+    pub fn for_instance(signature: &str) -> Self {
+        Self {
+            signature: signature.to_string(),
+            path: instance_dir(signature).join(".socket.sock"),
+        }
+    }
+
+    /// The event socket of the same instance.
+    pub fn event_socket(&self) -> PathBuf {
+        self.path.with_file_name(".socket2.sock")
+    }
+}
+
+impl HyprlandIpc for SocketClient {
+    fn request(&self, command: &str) -> io::Result<String> {
+        let mut stream = UnixStream::connect(&self.path)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        stream.write_all(command.as_bytes())?;
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply)?;
+        Ok(reply)
+    }
+}
+
+/// An event from the event socket, whose lines read `name>>data`, e.g. `monitoradded>>DP-1`.
+#[derive(Clone, Debug)]
+pub struct Event {
+    pub name: String,
+}
+
+/// Read events from `path` on the GTK main loop and hand each to `handler`, until the
+/// compositor closes the socket.
+pub async fn listen_events(
+    path: PathBuf,
+    handler: impl Fn(Event) + 'static,
+) -> Result<(), glib::Error> {
+    let address = gio::UnixSocketAddress::new(&path);
+    let connection = gio::SocketClient::new().connect_future(&address).await?;
+    let input = gio::DataInputStream::new(&connection.input_stream());
+    while let Some(line) = input.read_line_utf8_future(glib::Priority::DEFAULT).await? {
+        if let Some((name, _)) = line.split_once(">>") {
+            handler(Event {
+                name: name.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Directory holding the sockets of an instance: `$XDG_RUNTIME_DIR/hypr/<signature>`,
+/// or `/tmp/hypr/<signature>` on Hyprland versions before 0.40.
+pub fn instance_dir(signature: &str) -> PathBuf {
+    let runtime_dir = env::var("XDG_RUNTIME_DIR")
+        .map(|dir| PathBuf::from(dir).join("hypr").join(signature))
+        .ok();
+    match runtime_dir {
+        Some(dir) if dir.exists() => dir,
+        _ => PathBuf::from("/tmp/hypr").join(signature),
+    }
+}
+
+/// A running Hyprland instance found in the runtime directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instance {
+    pub signature: String,
+    pub pid: Option<u32>,
+    /// Wayland socket name, e.g. `wayland-1`.
+    pub wayland_socket: Option<String>,
+}
+
+impl Instance {
+    pub fn label(&self) -> String {
+        match (&self.wayland_socket, self.pid) {
+            (Some(socket), Some(pid)) => format!("{} (pid {})", socket, pid),
+            (Some(socket), None) => socket.clone(),
+            _ => self.signature.chars().take(12).collect(),
+        }
+    }
+}
+
+/// Instances whose request socket exists and whose process is still alive, like
+/// `hyprctl instances` reports them.
+pub fn instances() -> Vec<Instance> {
+    let mut roots = vec![PathBuf::from("/tmp/hypr")];
+    if let Ok(dir) = env::var("XDG_RUNTIME_DIR") {
+        roots.insert(0, PathBuf::from(dir).join("hypr"));
+    }
+    let mut found: Vec<Instance> = Vec::new();
+    for root in roots {
+        let Ok(entries) = std::fs::read_dir(&root) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            let signature = entry.file_name().to_string_lossy().to_string();
+            if !dir.join(".socket.sock").exists() || found.iter().any(|i| i.signature == signature)
+            {
+                continue;
+            }
+            // hyprland.lock holds the pid and the Wayland socket name, one per line
+            let lock = std::fs::read_to_string(dir.join("hyprland.lock")).unwrap_or_default();
+            let mut lines = lock.lines().map(str::trim);
+            let pid = lines.next().and_then(|l| l.parse::<u32>().ok());
+            let wayland_socket = lines.next().filter(|l| !l.is_empty()).map(str::to_string);
+            if let Some(pid) = pid
+                && !PathBuf::from(format!("/proc/{}", pid)).exists()
+            {
+                continue;
+            }
+            found.push(Instance {
+                signature,
+                pid,
+                wayland_socket,
+            });
+        }
+    }
+    found
+}
+
+/// Where requests go.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    Instance(String),
+    /// No compositor: runtime features are turned off.
+    Offline,
+}
+
+/// The instance this process runs in, else the first one found, else offline.
+pub fn default_target() -> Target {
+    let running = instances();
+    env::var("HYPRLAND_INSTANCE_SIGNATURE")
+        .ok()
+        .filter(|sig| running.iter().any(|i| &i.signature == sig))
+        .or_else(|| running.first().map(|i| i.signature.clone()))
+        .map(Target::Instance)
+        .unwrap_or(Target::Offline)
+}
+
+impl Target {
+    /// The client for this target, or `None` when offline.
+    pub fn client(&self) -> Option<Arc<SocketClient>> {
+        match self {
+            Target::Instance(signature) => Some(Arc::new(SocketClient::for_instance(signature))),
+            Target::Offline => None,
+        }
+    }
+
+    /// The target `client` sends its requests to.
+    pub fn of(client: Option<&Arc<SocketClient>>) -> Target {
+        client.map_or(Target::Offline, |c| Target::Instance(c.signature.clone()))
+    }
+}
+
+/// Run a request on a worker thread and await it from the GTK main loop.
+pub async fn call<C, T, F>(client: Arc<C>, request: F) -> io::Result<T>
+where
+    C: HyprlandIpc + 'static,
+    T: Send + 'static,
+    F: FnOnce(&C) -> io::Result<T> + Send + 'static,
+{
+    gio::spawn_blocking(move || request(&client))
+        .await
+        .unwrap_or_else(|_| Err(io::Error::other("IPC worker panicked")))
+}
+
+/// Split concatenated JSON replies, skipping plain-text errors between them.
+fn parse_concatenated<T: DeserializeOwned>(reply: &str) -> Vec<T> {
+    let mut values = Vec::new();
+    let mut rest = reply;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start..];
+        let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<T>();
+        match stream.next() {
+            Some(Ok(value)) => {
+                values.push(value);
+                rest = &rest[stream.byte_offset()..];
+            }
+            _ => rest = &rest[1..],
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    const MONITORS: &str = r#"[{
+        "id": 0, "name": "eDP-1", "description": "BOE 0x095F", "make": "BOE", "model": "0x095F",
+        "serial": "", "width": 2256, "height": 1504, "refreshRate": 59.99900, "x": 0, "y": 0,
+        "activeWorkspace": {"id": 1, "name": "1"}, "reserved": [0, 30, 0, 0], "scale": 1.33,
+        "transform": 0, "focused": true, "dpmsStatus": true, "vrr": false, "disabled": false,
+        "currentFormat": "XRGB2101010", "mirrorOf": "none",
+        "availableModes": ["2256x1504@60.00Hz", "2256x1504@48.00Hz"]
+    }]"#;
+    const BORDER_SIZE: &str = r#"{"option": "general:border_size", "int": 2, "set": true}"#;
+    const BATCH: &str = r#"{"option": "general:col.active_border", "int": -1, "set": false}
+
+no such option
+{"option": "decoration:rounding", "custom": "10 ", "set": true}"#;
+
+    /// A fake compositor socket in a temp dir, answering each request with the fixture
+    /// reply for that exact command.
+    fn serve(name: &str) -> SocketClient {
+        let dir = env::temp_dir().join(format!("hyprgui-ipc-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".socket.sock");
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buffer = [0; 4096];
+                let len = stream.read(&mut buffer).unwrap_or(0);
+                let reply = match &buffer[..len] {
+                    b"j/monitors all" => MONITORS,
+                    b"[[BATCH]]j/getoption general:border_size" => BORDER_SIZE,
+                    b"[[BATCH]]j/getoption general:col.active_border;j/getoption nope;j/getoption decoration:rounding" => BATCH,
+                    _ => "unknown request",
+                };
+                let _ = stream.write_all(reply.as_bytes());
+            }
+        });
+        SocketClient {
+            signature: name.to_string(),
+            path,
+        }
+    }
+
+    #[test]
+    fn parses_monitors() {
+        let monitors = serve("monitors").monitors().unwrap();
+        assert_eq!(monitors.len(), 1);
+        let monitor = &monitors[0];
+        assert_eq!(monitor.name, "eDP-1");
+        assert_eq!(monitor.description, "BOE 0x095F");
+        assert_eq!((monitor.width, monitor.height), (2256, 1504));
+        assert!((monitor.refresh_rate - 59.999).abs() < 1e-6);
+        assert!((monitor.scale - 1.33).abs() < 1e-6);
+        assert!(monitor.focused && monitor.dpms_status && !monitor.disabled);
+        assert_eq!(monitor.current_format, "XRGB2101010");
+        assert_eq!(monitor.mirror_of, "none");
+        assert_eq!(monitor.available_modes.len(), 2);
+    }
+
+    #[test]
+    fn parses_getoption() {
+        let keys = ["general:border_size".to_string()];
+        let options = serve("getoption").getoptions(&keys).unwrap();
+        assert_eq!(options[0].option, "general:border_size");
+        assert_eq!(options[0].to_config_string().as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn batches_getoptions_and_skips_errors() {
+        let keys = ["general:col.active_border", "nope", "decoration:rounding"].map(String::from);
+        let options = serve("batch").getoptions(&keys).unwrap();
+        let values: Vec<Option<String>> =
+            options.iter().map(OptionValue::to_config_string).collect();
+        assert_eq!(
+            values,
+            [Some("0xffffffff".to_string()), Some("10".to_string())]
+        );
+    }
+
+    #[test]
+    fn reports_unexpected_replies() {
+        let error = serve("error")
+            .request_json::<OptionValue>("j/getoption general:nope")
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,987 @@
+use gtk::{Box, Button, DropDown, Entry, ListBox, Orientation, prelude::*};
+
+use hyprparser::HyprlandConfig;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::config_lines::{lines_mut, source_label, source_lines};
+use crate::monitors::{select_string, selected_string, set_error, set_strings, string_dropdown};
+use crate::widgets::{notify_changes, set_conflict, set_modified};
+
+/// Every flag a `bind` keyword can carry, e.g. `bindel` is `bind` with `e` and `l`.
+const FLAGS: &[(char, &str)] = &[
+    ('l', "locked, also works on the lock screen"),
+    ('r', "release, triggers when the key is released"),
+    (
+        'c',
+        "click, triggers when a mouse button is released over the window",
+    ),
+    (
+        'g',
+        "drag, triggers when a mouse button is released after dragging",
+    ),
+    ('o', "long press, triggers after holding the key"),
+    ('e', "repeat, repeats while the key is held"),
+    ('n', "non-consuming, the key is passed on to the window too"),
+    ('m', "mouse, for movewindow and resizewindow"),
+    ('t', "transparent, can't be shadowed by other binds"),
+    ('i', "ignore modifiers"),
+    ('s', "separate, combines keys between modifiers"),
+    ('d', "has a description"),
+    ('p', "bypasses the app's requests to inhibit keybinds"),
+];
+
+/// Dispatchers offered in the dropdown. Others found in the files are added to it.
+const DISPATCHERS: &[&str] = &[
+    "exec",
+    "execr",
+    "pass",
+    "sendshortcut",
+    "sendkeystate",
+    "killactive",
+    "forcekillactive",
+    "closewindow",
+    "killwindow",
+    "signal",
+    "signalwindow",
+    "workspace",
+    "movetoworkspace",
+    "movetoworkspacesilent",
+    "togglefloating",
+    "setfloating",
+    "settiled",
+    "fullscreen",
+    "fullscreenstate",
+    "dpms",
+    "pin",
+    "movefocus",
+    "movewindow",
+    "resizewindow",
+    "swapwindow",
+    "centerwindow",
+    "resizeactive",
+    "moveactive",
+    "resizewindowpixel",
+    "movewindowpixel",
+    "cyclenext",
+    "swapnext",
+    "tagwindow",
+    "focuswindow",
+    "focusmonitor",
+    "splitratio",
+    "movecursortocorner",
+    "movecursor",
+    "renameworkspace",
+    "exit",
+    "forcerendererreload",
+    "movecurrentworkspacetomonitor",
+    "focusworkspaceoncurrentmonitor",
+    "moveworkspacetomonitor",
+    "swapactiveworkspaces",
+    "bringactivetotop",
+    "alterzorder",
+    "togglespecialworkspace",
+    "focusurgentorlast",
+    "togglegroup",
+    "changegroupactive",
+    "focuscurrentorlast",
+    "lockgroups",
+    "lockactivegroup",
+    "moveintogroup",
+    "moveoutofgroup",
+    "movewindoworgroup",
+    "movegroupwindow",
+    "denywindowfromgroup",
+    "setignoregrouplock",
+    "global",
+    "submap",
+    "event",
+    "setprop",
+    "toggleswallow",
+    "layoutmsg",
+    "pseudo",
+    "togglesplit",
+    "swapsplit",
+];
+
+/// Where a bind line was read from, so it can be found again on save.
+#[derive(Clone, Debug)]
+pub struct BindOrigin {
+    /// `None` for the main config file, otherwise an index into `sourced_content`.
+    pub source: Option<usize>,
+    /// The trimmed line as it currently exists in that file.
+    pub line: String,
+    /// The line it was read from, which tells identical binds in one file apart.
+    pub index: usize,
+}
+
+/// The file and submap a bind belongs to. Binds are only reordered within their group,
+/// since moving one past a `submap =` line would change when it applies.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BindGroup {
+    pub source: Option<usize>,
+    /// Empty outside of any submap.
+    pub submap: String,
+    /// Shown in the row tooltip.
+    pub file: String,
+}
+
+/// A bind line as read from a file.
+pub struct Bind {
+    pub origin: BindOrigin,
+    pub group: BindGroup,
+    pub flags: String,
+    pub value: String,
+}
+
+pub struct BindRow {
+    row: Box,
+    origin: Option<BindOrigin>,
+    group: BindGroup,
+    /// Index among the binds when the page was loaded, to notice reordering.
+    order: Option<usize>,
+    saved: String,
+    flags: Entry,
+    modifiers: Entry,
+    key: Entry,
+    description: Entry,
+    dispatcher: DropDown,
+    argument: Entry,
+    up: Button,
+    down: Button,
+}
+
+impl BindRow {
+    /// The line as it should be written, e.g. `bindd = SUPER, Q, Close, killactive`.
+    pub fn line(&self) -> String {
+        let flags = self.flags.text().trim().to_string();
+        let mut fields = vec![
+            self.modifiers.text().trim().to_string(),
+            self.key.text().trim().to_string(),
+        ];
+        if flags.contains('d') {
+            fields.push(self.description.text().trim().to_string());
+        }
+        fields.push(selected_string(&self.dispatcher).unwrap_or_default());
+        let argument = self.argument.text().trim().to_string();
+        if !argument.is_empty() {
+            fields.push(argument);
+        }
+        format!("bind{} = {}", flags, fields.join(", "))
+    }
+
+    fn is_modified(&self) -> bool {
+        self.origin.is_none() || self.line() != self.saved
+    }
+
+    /// Show `line` in the row's fields.
+    fn show_line(&self, line: &str) {
+        let Some((keyword, value)) = split_line(line) else {
+            return;
+        };
+        let flags = bind_flags(keyword).unwrap_or_default();
+        let [modifiers, key, description, dispatcher, argument] =
+            split_fields(value, flags.contains('d'));
+        self.flags.set_text(flags);
+        self.modifiers.set_text(&modifiers);
+        self.key.set_text(&key);
+        self.description.set_text(&description);
+        if !dispatcher.is_empty() {
+            let dispatchers = DISPATCHERS.iter().map(|d| d.to_string()).collect();
+            set_strings(&self.dispatcher, dispatchers, Some(&dispatcher));
+        }
+        self.argument.set_text(&argument);
+    }
+
+    /// The text to put at a line currently holding `slot`, keeping the slot's indentation
+    /// and this bind's own comment.
+    fn text_at(&self, slot: &str) -> String {
+        let indent: String = slot.chars().take_while(|c| c.is_whitespace()).collect();
+        match &self.origin {
+            Some(origin) if !self.is_modified() && origin.line == slot.trim() => slot.to_string(),
+            Some(origin) if !self.is_modified() => format!("{}{}", indent, origin.line),
+            Some(origin) => match comment(&origin.line) {
+                Some(comment) => format!("{}{} {}", indent, self.line(), comment),
+                None => format!("{}{}", indent, self.line()),
+            },
+            None => format!("{}{}", indent, self.line()),
+        }
+    }
+}
+
+/// A row of the Keybinds page as it was before the files were reloaded.
+struct EditedBind {
+    origin: Option<BindOrigin>,
+    group: BindGroup,
+    line: String,
+    modified: bool,
+}
+
+/// The Keybinds rows in table order plus the deleted lines, to carry over to the rows
+/// read after the files were reloaded.
+#[derive(Default)]
+pub struct BindEdits {
+    rows: Vec<EditedBind>,
+    removed: Vec<BindOrigin>,
+    reordered: bool,
+}
+
+impl BindEdits {
+    fn is_empty(&self) -> bool {
+        self.removed.is_empty() && !self.reordered && !self.rows.iter().any(|r| r.modified)
+    }
+}
+
+/// Rows of the Keybinds page plus the lines deleted since the last save.
+#[derive(Clone, Default)]
+pub struct BindTable {
+    pub rows: Rc<RefCell<Vec<BindRow>>>,
+    pub removed: Rc<RefCell<Vec<BindOrigin>>>,
+}
+
+impl BindTable {
+    pub fn is_modified(&self) -> bool {
+        let rows = self.rows.borrow();
+        !self.removed.borrow().is_empty()
+            || rows.iter().any(|r| r.is_modified())
+            || !rows.iter().filter_map(|r| r.order).is_sorted()
+    }
+
+    /// Mirror the table into `changed_options` so the save path picks it up.
+    pub fn record_changes(
+        &self,
+        changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+        list: &ListBox,
+    ) {
+        let key = ("keybinds".to_string(), "bind".to_string());
+        if self.is_modified() {
+            let lines: Vec<String> = self.rows.borrow().iter().map(|r| r.line()).collect();
+            changed_options.borrow_mut().insert(key, lines.join("\n"));
+        } else {
+            changed_options.borrow_mut().remove(&key);
+        }
+
+        let rows = self.rows.borrow();
+        for (idx, row) in rows.iter().enumerate() {
+            set_modified(&row.row, row.is_modified());
+            let same_group = |other: Option<&BindRow>| other.is_some_and(|o| o.group == row.group);
+            row.up
+                .set_sensitive(idx > 0 && same_group(rows.get(idx - 1)));
+            row.down.set_sensitive(same_group(rows.get(idx + 1)));
+        }
+        drop(rows);
+        notify_changes(list);
+    }
+
+    /// The rows, edits and deletions since the table was loaded.
+    pub fn pending_edits(&self) -> BindEdits {
+        let rows = self.rows.borrow();
+        BindEdits {
+            rows: rows
+                .iter()
+                .map(|r| EditedBind {
+                    origin: r.origin.clone(),
+                    group: r.group.clone(),
+                    line: r.line(),
+                    modified: r.is_modified(),
+                })
+                .collect(),
+            removed: self.removed.borrow().clone(),
+            reordered: !rows.iter().filter_map(|r| r.order).is_sorted(),
+        }
+    }
+
+    /// Apply `edits` to the rows just read from the files, in the order the edited table
+    /// had them. An edit lands on the bind read from the same line, or else on the one
+    /// for the same keys in the same file and submap, which changed on disk too and gets
+    /// flagged. Returns the number of flagged rows.
+    pub fn replay_edits(
+        &self,
+        list: &ListBox,
+        edits: BindEdits,
+        changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+    ) -> usize {
+        if edits.is_empty() {
+            return 0;
+        }
+        let reloaded: Vec<(Option<BindOrigin>, BindGroup, String, Box)> = self
+            .rows
+            .borrow()
+            .iter()
+            .map(|r| {
+                let origin = r.origin.clone();
+                (origin, r.group.clone(), r.saved.clone(), r.row.clone())
+            })
+            .collect();
+        let mut claimed = HashSet::new();
+        let mut counterpart = |origin: &BindOrigin, group: Option<&BindGroup>| {
+            let unclaimed = || {
+                reloaded
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, _)| !claimed.contains(idx))
+            };
+            let same_line = |o: &BindOrigin| o.source == origin.source && o.line == origin.line;
+            let found = unclaimed()
+                .find(|(_, (o, ..))| {
+                    o.as_ref()
+                        .is_some_and(|o| same_line(o) && o.index == origin.index)
+                })
+                .or_else(|| unclaimed().find(|(_, (o, ..))| o.as_ref().is_some_and(same_line)))
+                .map(|(idx, _)| (idx, false))
+                .or_else(|| {
+                    let keys = bound_keys(&origin.line)?;
+                    unclaimed()
+                        .find(|(_, (o, g, saved, _))| {
+                            o.as_ref().is_some_and(|o| o.source == origin.source)
+                                && group.is_none_or(|group| group.submap == g.submap)
+                                && bound_keys(saved).as_ref() == Some(&keys)
+                        })
+                        .map(|(idx, _)| (idx, true))
+                });
+            if let Some((idx, _)) = found {
+                claimed.insert(idx);
+            }
+            found
+        };
+
+        let mut conflicts = 0;
+        // The rows in the order the edited table had them
+        let mut placed: Vec<Box> = Vec::new();
+        for bind in edits.rows {
+            let found = bind
+                .origin
+                .as_ref()
+                .and_then(|origin| counterpart(origin, Some(&bind.group)));
+            let row = match found {
+                Some((idx, _)) => reloaded[idx].3.clone(),
+                // Unchanged binds gone from the file stay gone
+                None if bind.origin.is_some() && !bind.modified => continue,
+                None => {
+                    let position = self
+                        .rows
+                        .borrow()
+                        .iter()
+                        .rposition(|r| r.group == bind.group)
+                        .map_or(usize::MAX, |idx| idx + 1);
+                    add_bind_row(list, position, None, bind.group, self, changed_options);
+                    let rows = self.rows.borrow();
+                    let idx = position.min(rows.len() - 1);
+                    rows[idx].row.clone()
+                }
+            };
+            if bind.modified {
+                if let Some(target) = self.rows.borrow().iter().find(|r| r.row == row) {
+                    target.show_line(&bind.line);
+                }
+                let tooltip = match found {
+                    Some((_, false)) => None,
+                    Some((idx, true)) => Some(format!(
+                        "Also changed on disk to \"{}\". Saving keeps your bind.",
+                        reloaded[idx].2
+                    )),
+                    None if bind.origin.is_some() => {
+                        Some("Removed from the file on disk. Saving adds it back.".to_string())
+                    }
+                    None => None,
+                };
+                if let Some(tooltip) = tooltip {
+                    conflicts += 1;
+                    set_conflict(&row, &tooltip);
+                }
+            }
+            placed.push(row);
+        }
+
+        for origin in &edits.removed {
+            match counterpart(origin, None) {
+                Some((idx, false)) => self.remove_row(list, &reloaded[idx].3),
+                Some((idx, true)) => {
+                    conflicts += 1;
+                    set_conflict(
+                        &reloaded[idx].3,
+                        &format!(
+                            "Changed on disk to \"{}\" after you deleted it. Delete it again to drop it.",
+                            reloaded[idx].2
+                        ),
+                    );
+                }
+                None => {}
+            }
+        }
+
+        self.arrange(list, &placed);
+        self.record_changes(changed_options, list);
+        conflicts
+    }
+
+    /// Put the `placed` rows in their given order, each group keeping the positions its
+    /// rows occupy. Other rows stay where they are.
+    fn arrange(&self, list: &ListBox, placed: &[Box]) {
+        let order: Vec<Box> = {
+            let rows = self.rows.borrow();
+            let mut queues: Vec<(&BindGroup, Vec<&Box>)> = Vec::new();
+            for row in placed {
+                let Some(bind) = rows.iter().find(|r| r.row == *row) else {
+                    continue;
+                };
+                match queues.iter_mut().find(|(group, _)| **group == bind.group) {
+                    Some((_, queue)) => queue.push(row),
+                    None => queues.push((&bind.group, vec![row])),
+                }
+            }
+            for (_, queue) in &mut queues {
+                queue.reverse();
+            }
+            rows.iter()
+                .map(|r| {
+                    let queue = placed
+                        .contains(&r.row)
+                        .then(|| queues.iter_mut().find(|(group, _)| **group == r.group))
+                        .flatten();
+                    match queue.and_then(|(_, queue)| queue.pop()) {
+                        Some(row) => row.clone(),
+                        None => r.row.clone(),
+                    }
+                })
+                .collect()
+        };
+
+        let mut rows = self.rows.borrow_mut();
+        if rows.iter().map(|r| &r.row).eq(order.iter()) {
+            return;
+        }
+        let list_rows: Vec<gtk::ListBoxRow> = order
+            .iter()
+            .filter_map(|row| row.parent().and_downcast::<gtk::ListBoxRow>())
+            .collect();
+        for list_row in &list_rows {
+            list.remove(list_row);
+        }
+        for list_row in &list_rows {
+            list.append(list_row);
+        }
+        let mut unordered = std::mem::take(&mut *rows);
+        for row in &order {
+            if let Some(idx) = unordered.iter().position(|r| r.row == *row) {
+                rows.push(unordered.remove(idx));
+            }
+        }
+    }
+
+    /// Drop the row holding `row` from the table and the list, remembering its line so
+    /// the save deletes it.
+    fn remove_row(&self, list: &ListBox, row: &Box) {
+        let mut rows = self.rows.borrow_mut();
+        if let Some(idx) = rows.iter().position(|r| r.row == *row) {
+            let removed = rows.remove(idx);
+            self.removed.borrow_mut().extend(removed.origin);
+        }
+        drop(rows);
+        if let Some(ancestor) = row.ancestor(gtk::ListBoxRow::static_type())
+            && let Some(lb_row) = ancestor.downcast_ref::<gtk::ListBoxRow>()
+        {
+            list.remove(lb_row);
+        }
+    }
+
+    /// The group of the row at `idx`.
+    pub fn group_at(&self, idx: usize) -> Option<BindGroup> {
+        self.rows.borrow().get(idx).map(|r| r.group.clone())
+    }
+
+    /// The row of the bind on line `idx` of `source`, whose content is `lines`.
+    pub fn row_at(&self, source: Option<usize>, lines: &[String], idx: usize) -> Option<Box> {
+        let rows = self.rows.borrow();
+        let origins = rows
+            .iter()
+            .filter_map(|r| Some((r.origin.as_ref().filter(|o| o.source == source)?, r)));
+        let mut used = HashSet::new();
+        for (origin, row) in origins {
+            if let Some(found) = locate(lines, origin, &used) {
+                if found == idx {
+                    return Some(row.row.clone());
+                }
+                used.insert(found);
+            }
+        }
+        None
+    }
+
+    /// Swap the row at `idx` with its neighbour at `other`, in the table and the list.
+    fn swap(&self, list: &ListBox, idx: usize, other: usize) {
+        let mut rows = self.rows.borrow_mut();
+        if other >= rows.len() || rows[idx].group != rows[other].group {
+            return;
+        }
+        rows.swap(idx, other);
+        if let Some(list_row) = list.row_at_index(idx as i32) {
+            list.remove(&list_row);
+            list.insert(&list_row, other as i32);
+        }
+    }
+
+    /// Rewrite, remove, add and reorder bind lines in the file each row belongs to. Each
+    /// group's rows fill the lines its binds held, in table order; new rows follow them.
+    pub fn apply(&self, config: &mut HyprlandConfig) {
+        let rows = self.rows.borrow();
+        let removed = self.removed.borrow();
+        let sources: Vec<Option<usize>> = std::iter::once(None)
+            .chain((0..config.sourced_content.len()).map(Some))
+            .collect();
+        for source in sources {
+            let Some(lines) = lines_mut(config, source) else {
+                continue;
+            };
+            let mut used = HashSet::new();
+            let mut deleted = HashSet::new();
+            for origin in removed.iter().filter(|o| o.source == source) {
+                if let Some(idx) = locate(lines, origin, &used) {
+                    used.insert(idx);
+                    deleted.insert(idx);
+                }
+            }
+
+            let mut groups: Vec<(&str, Vec<&BindRow>)> = Vec::new();
+            for row in rows.iter().filter(|r| r.group.source == source) {
+                match groups.iter_mut().find(|(s, _)| *s == row.group.submap) {
+                    Some((_, members)) => members.push(row),
+                    None => groups.push((&row.group.submap, vec![row])),
+                }
+            }
+
+            let mut replaced: HashMap<usize, String> = HashMap::new();
+            // Lines to add after the given index, `usize::MAX` being the end of the file
+            let mut inserted: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+            for (submap, members) in groups {
+                let mut slots: Vec<usize> = Vec::new();
+                for row in &members {
+                    if let Some(origin) = &row.origin
+                        && let Some(idx) = locate(lines, origin, &used)
+                    {
+                        used.insert(idx);
+                        slots.push(idx);
+                    }
+                }
+                slots.sort_unstable();
+                let anchor = match slots.last() {
+                    Some(last) => *last,
+                    None if submap.is_empty() => usize::MAX,
+                    None => lines
+                        .iter()
+                        .position(|l| submap_value(l).as_deref() == Some(submap))
+                        .unwrap_or(usize::MAX),
+                };
+                let indent = slots.last().map(|idx| lines[*idx].as_str()).unwrap_or("");
+                for (idx, row) in members.iter().enumerate() {
+                    match slots.get(idx) {
+                        Some(slot) => {
+                            replaced.insert(*slot, row.text_at(&lines[*slot]));
+                        }
+                        None => inserted
+                            .entry(anchor)
+                            .or_default()
+                            .push(row.text_at(indent)),
+                    }
+                }
+            }
+
+            let mut updated = Vec::with_capacity(lines.len());
+            for (idx, line) in lines.iter().enumerate() {
+                if !deleted.contains(&idx) {
+                    updated.push(replaced.remove(&idx).unwrap_or_else(|| line.clone()));
+                }
+                if let Some(extra) = inserted.remove(&idx) {
+                    updated.extend(extra);
+                }
+            }
+            for extra in inserted.into_values() {
+                updated.extend(extra);
+            }
+            *lines = updated;
+        }
+    }
+}
+
+/// The flags of a `bind` keyword, or `None` for other keywords.
+fn bind_flags(keyword: &str) -> Option<&str> {
+    keyword
+        .strip_prefix("bind")
+        .filter(|flags| flags.chars().all(|c| c.is_ascii_lowercase()))
+}
+
+/// Flags Hyprland doesn't know.
+fn unknown_flags(flags: &str) -> Vec<char> {
+    flags
+        .chars()
+        .filter(|c| !FLAGS.iter().any(|(flag, _)| flag == c))
+        .collect()
+}
+
+/// The comment of a line, where `##` is an escaped `#` rather than a comment.
+fn comment(line: &str) -> Option<&str> {
+    let bytes = line.as_bytes();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'#' {
+            if bytes.get(idx + 1) == Some(&b'#') {
+                idx += 2;
+                continue;
+            }
+            return Some(&line[idx..]);
+        }
+        idx += 1;
+    }
+    None
+}
+
+/// The keyword and value of a line, without its comment.
+fn split_line(line: &str) -> Option<(&str, &str)> {
+    let line = match comment(line) {
+        Some(comment) => &line[..line.len() - comment.len()],
+        None => line,
+    };
+    let (key, value) = line.split_once('=')?;
+    Some((key.trim(), value.trim()))
+}
+
+/// The submap a `submap = name` line enters, empty for `submap = reset`.
+fn submap_value(line: &str) -> Option<String> {
+    let (key, value) = split_line(line)?;
+    (key == "submap").then(|| {
+        if value == "reset" {
+            String::new()
+        } else {
+            value.to_string()
+        }
+    })
+}
+
+/// The line `origin` was read from while it still holds the bind, or else the first line
+/// holding it that no other row has claimed yet.
+fn locate(lines: &[String], origin: &BindOrigin, used: &HashSet<usize>) -> Option<usize> {
+    let holds = |idx: &usize| !used.contains(idx) && lines[*idx].trim() == origin.line;
+    Some(origin.index)
+        .filter(|idx| *idx < lines.len() && holds(idx))
+        .or_else(|| (0..lines.len()).find(holds))
+}
+
+/// Collect the bind lines in the order Hyprland reads them: a sourced file's binds come
+/// in at its `source =` line, and a `submap =` carries on into and out of it.
+pub fn collect_binds(config: &HyprlandConfig) -> Vec<Bind> {
+    let positions = source_lines(config);
+    let mut binds = Vec::new();
+    let mut submap = String::new();
+    let main_file = source_label(config, None);
+    for (idx, line) in config.content.iter().enumerate() {
+        read_bind(&mut binds, &mut submap, (None, idx), &main_file, line);
+        for (source, _) in positions
+            .iter()
+            .enumerate()
+            .filter(|(_, at)| **at == Some(idx))
+        {
+            read_file(config, source, &mut binds, &mut submap);
+        }
+    }
+    // Sourced files whose `source =` line wasn't recognised
+    for (source, _) in positions.iter().enumerate().filter(|(_, at)| at.is_none()) {
+        read_file(config, source, &mut binds, &mut submap);
+    }
+    binds
+}
+
+/// Add the binds of the sourced file at `source`.
+fn read_file(config: &HyprlandConfig, source: usize, binds: &mut Vec<Bind>, submap: &mut String) {
+    let file = source_label(config, Some(source));
+    let lines = config.sourced_content.get(source).into_iter().flatten();
+    for (idx, line) in lines.enumerate() {
+        read_bind(binds, submap, (Some(source), idx), &file, line);
+    }
+}
+
+/// Add the bind on `line`, at `index` in `source`, to `binds`, or follow the submap it enters.
+fn read_bind(
+    binds: &mut Vec<Bind>,
+    submap: &mut String,
+    (source, index): (Option<usize>, usize),
+    file: &str,
+    line: &str,
+) {
+    if let Some(name) = submap_value(line) {
+        *submap = name;
+        return;
+    }
+    let Some((keyword, value)) = split_line(line) else {
+        return;
+    };
+    let Some(flags) = bind_flags(keyword) else {
+        return;
+    };
+    binds.push(Bind {
+        origin: BindOrigin {
+            source,
+            line: line.trim().to_string(),
+            index,
+        },
+        group: BindGroup {
+            source,
+            submap: submap.clone(),
+            file: file.to_string(),
+        },
+        flags: flags.to_string(),
+        value: value.to_string(),
+    });
+}
+
+/// Add a row for `bind`, or an empty one in `group`, at `position` in the list.
+pub fn add_bind_row(
+    list: &ListBox,
+    position: usize,
+    bind: Option<Bind>,
+    group: BindGroup,
+    table: &BindTable,
+    changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+) {
+    let (origin, flags, value) = match bind {
+        Some(bind) => (Some(bind.origin), bind.flags, bind.value),
+        None => (None, String::new(), String::new()),
+    };
+    let [modifiers_text, key_text, description, dispatcher, argument] =
+        split_fields(&value, flags.contains('d'));
+
+    let row = Box::new(Orientation::Horizontal, 6);
+    let flags_entry = Entry::new();
+    flags_entry.set_width_request(70);
+    flags_entry.set_max_width_chars(6);
+    flags_entry.set_text(&flags);
+    let flag_help: Vec<String> = FLAGS
+        .iter()
+        .map(|(flag, meaning)| format!("{}: {}", flag, meaning))
+        .collect();
+    flags_entry.set_tooltip_text(Some(&flag_help.join("\n")));
+    let modifiers = Entry::new();
+    modifiers.set_width_request(140);
+    modifiers.set_placeholder_text(Some("SUPER SHIFT"));
+    modifiers.set_text(&modifiers_text);
+    let key = Entry::new();
+    key.set_width_request(120);
+    key.set_placeholder_text(Some("Q or mouse:272"));
+    key.set_text(&key_text);
+    let description_entry = Entry::new();
+    description_entry.set_width_request(160);
+    description_entry.set_placeholder_text(Some("Needs the d flag"));
+    description_entry.set_text(&description);
+    let mut dispatchers: Vec<String> = DISPATCHERS.iter().map(|d| d.to_string()).collect();
+    if !dispatcher.is_empty() && !dispatchers.contains(&dispatcher) {
+        dispatchers.push(dispatcher.clone());
+    }
+    let dispatcher_dd = string_dropdown(&dispatchers);
+    dispatcher_dd.set_width_request(180);
+    if !dispatcher.is_empty() {
+        select_string(&dispatcher_dd, &dispatcher);
+    }
+    let argument_entry = Entry::new();
+    argument_entry.set_hexpand(true);
+    argument_entry.set_text(&argument);
+    let up = Button::from_icon_name("go-up-symbolic");
+    up.add_css_class("flat");
+    up.set_tooltip_text(Some("Move up"));
+    let down = Button::from_icon_name("go-down-symbolic");
+    down.add_css_class("flat");
+    down.set_tooltip_text(Some("Move down"));
+    for widget in [
+        flags_entry.upcast_ref::<gtk::Widget>(),
+        modifiers.upcast_ref(),
+        key.upcast_ref(),
+        description_entry.upcast_ref(),
+        dispatcher_dd.upcast_ref(),
+        argument_entry.upcast_ref(),
+        up.upcast_ref(),
+        down.upcast_ref(),
+    ] {
+        row.append(widget);
+    }
+    row.set_tooltip_text(Some(&if group.submap.is_empty() {
+        format!("Defined in {}", group.file)
+    } else {
+        format!("Defined in {}, submap {}", group.file, group.submap)
+    }));
+
+    let order = origin.as_ref().map(|_| table.rows.borrow().len());
+    let mut bind_row = BindRow {
+        row: row.clone(),
+        origin,
+        group,
+        order,
+        saved: String::new(),
+        flags: flags_entry.clone(),
+        modifiers: modifiers.clone(),
+        key: key.clone(),
+        description: description_entry.clone(),
+        dispatcher: dispatcher_dd.clone(),
+        argument: argument_entry.clone(),
+        up: up.clone(),
+        down: down.clone(),
+    };
+    bind_row.saved = bind_row.line();
+    check_row(&bind_row);
+
+    let table_clone = table.clone();
+    let changed = changed_options.clone();
+    let list_clone = list.clone();
+    let row_ref = row.clone();
+    let notify = move || {
+        if let Some(bind_row) = table_clone.rows.borrow().iter().find(|r| r.row == row_ref) {
+            check_row(bind_row);
+        }
+        table_clone.record_changes(&changed, &list_clone)
+    };
+    for entry in [
+        &flags_entry,
+        &modifiers,
+        &key,
+        &description_entry,
+        &argument_entry,
+    ] {
+        let n = notify.clone();
+        entry.connect_changed(move |_| n());
+    }
+    let n = notify.clone();
+    dispatcher_dd.connect_selected_notify(move |_| n());
+
+    for (button, step) in [(&up, -1), (&down, 1)] {
+        let table = table.clone();
+        let changed = changed_options.clone();
+        let list = list.clone();
+        let row_ref = row.clone();
+        button.connect_clicked(move |_| {
+            let idx = table.rows.borrow().iter().position(|r| r.row == row_ref);
+            if let Some(idx) = idx
+                && let Some(other) = idx.checked_add_signed(step)
+            {
+                table.swap(&list, idx, other);
+                table.record_changes(&changed, &list);
+            }
+        });
+    }
+
+    let del_btn = Button::from_icon_name("window-close-symbolic");
+    del_btn.set_has_frame(false);
+    del_btn.add_css_class("flat");
+    let table_clone = table.clone();
+    let changed = changed_options.clone();
+    let list_clone = list.clone();
+    let row_ref = row.clone();
+    del_btn.connect_clicked(move |_| {
+        table_clone.remove_row(&list_clone, &row_ref);
+        table_clone.record_changes(&changed, &list_clone);
+    });
+    row.append(&del_btn);
+
+    let position = position.min(table.rows.borrow().len());
+    list.insert(&row, position as i32);
+    let is_new = bind_row.origin.is_none();
+    table.rows.borrow_mut().insert(position, bind_row);
+    if is_new {
+        notify();
+    }
+}
+
+/// The modifiers and key a bind line binds, to recognise the bind after its line changed.
+fn bound_keys(line: &str) -> Option<(String, String)> {
+    let (keyword, value) = split_line(line)?;
+    let flags = bind_flags(keyword)?;
+    let [modifiers, key, ..] = split_fields(value, flags.contains('d'));
+    let modifiers = modifiers.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some((modifiers.to_uppercase(), key))
+}
+
+/// Split a bind value into modifiers, key, description, dispatcher and argument.
+/// `bind[flags] = MODS, KEY[, DESCRIPTION], DISPATCHER[, ARGUMENT]` is split the way
+/// Hyprland does: into 5 fields with the `d` flag and 4 without, the last one taking
+/// the rest of the line, commas included.
+fn split_fields(value: &str, described: bool) -> [String; 5] {
+    let field_count = if described { 5 } else { 4 };
+    let mut parts: Vec<String> = value
+        .splitn(field_count, ',')
+        .map(|p| p.trim().to_string())
+        .collect();
+    parts.resize(field_count, String::new());
+    if !described {
+        parts.insert(2, String::new());
+    }
+    parts.try_into().unwrap_or_default()
+}
+
+/// Flag unknown flags, missing keys and descriptions Hyprland would split at a comma,
+/// and only offer the description with `d`.
+fn check_row(row: &BindRow) {
+    let flags = row.flags.text();
+    let unknown = unknown_flags(flags.trim());
+    set_error(&row.flags, !unknown.is_empty());
+    set_error(&row.key, row.key.text().trim().is_empty());
+    let described = flags.contains('d');
+    row.description.set_sensitive(described);
+    let split = described && row.description.text().contains(',');
+    set_error(&row.description, split);
+    row.description
+        .set_tooltip_text(split.then_some("Hyprland ends the description at the first comma"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_commas_in_the_last_field() {
+        assert_eq!(
+            split_fields("SUPER, Return, exec, kitty -e sh -c 'a, b'", false),
+            ["SUPER", "Return", "", "exec", "kitty -e sh -c 'a, b'"]
+        );
+        assert_eq!(
+            split_fields("SUPER SHIFT, Q, Close window, killactive,", true),
+            ["SUPER SHIFT", "Q", "Close window", "killactive", ""]
+        );
+        assert_eq!(split_fields("SUPER, F", false), ["SUPER", "F", "", "", ""]);
+    }
+
+    #[test]
+    fn reads_the_bound_keys() {
+        assert_eq!(
+            bound_keys("bindel =  super  shift , XF86AudioRaiseVolume, exec, wpctl # volume"),
+            Some((
+                "SUPER SHIFT".to_string(),
+                "XF86AudioRaiseVolume".to_string()
+            ))
+        );
+        assert_eq!(
+            bound_keys("bindd = SUPER, Q, Close, killactive"),
+            Some(("SUPER".to_string(), "Q".to_string()))
+        );
+        assert_eq!(bound_keys("unbind = SUPER, Q"), None);
+        assert_eq!(bound_keys("bind3 = SUPER, Q, killactive"), None);
+    }
+
+    #[test]
+    fn locates_identical_binds_by_their_line() {
+        let lines: Vec<String> = [
+            "submap = resize",
+            "bind = , escape, submap, reset",
+            "submap = reset",
+            "submap = move",
+            "bind = , escape, submap, reset",
+        ]
+        .map(str::to_string)
+        .to_vec();
+        let origin = |index| BindOrigin {
+            source: None,
+            line: "bind = , escape, submap, reset".to_string(),
+            index,
+        };
+        assert_eq!(locate(&lines, &origin(4), &HashSet::new()), Some(4));
+        assert_eq!(locate(&lines, &origin(1), &HashSet::new()), Some(1));
+        // After a reload moved the lines, the first free match is taken
+        assert_eq!(locate(&lines, &origin(0), &HashSet::from([1])), Some(4));
+    }
+}
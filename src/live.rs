@@ -0,0 +1,203 @@
+use gtk::glib;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::ipc::{self, HyprlandRequests, SocketClient};
+
+/// Quiet period before queued values are sent, so dragging a slider sends one batch.
+const FLUSH_DELAY: Duration = Duration::from_millis(60);
+
+/// Option values pushed to the running compositor as runtime keywords, along with
+/// what they replaced so they can be put back.
+#[derive(Default)]
+pub struct LivePreview {
+    enabled: bool,
+    /// Values currently pushed, keyed by `section:key`.
+    pushed: HashMap<String, String>,
+    /// Values the compositor had for pushed options, queried before the first push.
+    /// Empty for options it doesn't know.
+    originals: HashMap<String, String>,
+    queue: Vec<(String, String)>,
+    /// Set from scheduling a flush until its batch was sent, so batches go out in order.
+    flush_scheduled: bool,
+    /// Bumped by `forget`, so a flush in flight doesn't record stale originals.
+    generation: u64,
+}
+
+impl LivePreview {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Bring the compositor in line with `wanted` (key and value): new values are pushed,
+    /// and options no longer wanted get their original back.
+    pub fn sync(&mut self, wanted: HashMap<String, String>) {
+        for (key, value) in &wanted {
+            if self.pushed.get(key) != Some(value) {
+                self.pushed.insert(key.clone(), value.clone());
+                self.queue.push((key.clone(), value.clone()));
+            }
+        }
+
+        let stale: Vec<String> = self
+            .pushed
+            .keys()
+            .filter(|key| !wanted.contains_key(*key))
+            .cloned()
+            .collect();
+        for key in stale {
+            self.pushed.remove(&key);
+            match self.originals.remove(&key) {
+                Some(original) if !original.is_empty() => self.queue.push((key, original)),
+                Some(_) => {}
+                // Never sent, as originals are queried before the first push
+                None => self.queue.retain(|(queued, _)| *queued != key),
+            }
+        }
+    }
+
+    /// Queue the original value of every pushed option.
+    pub fn revert(&mut self) {
+        self.pushed.clear();
+        let originals = &self.originals;
+        self.queue.retain(|(key, _)| originals.contains_key(key));
+        self.queue.extend(
+            self.originals
+                .drain()
+                .filter(|(_, value)| !value.is_empty()),
+        );
+    }
+
+    /// Drop the record of pushed values, e.g. after the files were reloaded and the
+    /// compositor picked them up itself.
+    pub fn forget(&mut self) {
+        self.pushed.clear();
+        self.originals.clear();
+        self.queue.clear();
+        self.generation += 1;
+    }
+
+    /// Queued options whose value before live preview isn't known yet.
+    fn missing_originals(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .queue
+            .iter()
+            .map(|(key, _)| key.clone())
+            .filter(|key| !self.originals.contains_key(key))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Record the runtime values queried for `keys`, and queue the original of options
+    /// that stopped being wanted while the query ran.
+    fn settle(&mut self, keys: Vec<String>, mut current: HashMap<String, String>) {
+        for key in keys {
+            let original = current.remove(&key).unwrap_or_default();
+            self.originals.entry(key).or_insert(original);
+        }
+        let stale: Vec<String> = self
+            .originals
+            .keys()
+            .filter(|key| !self.pushed.contains_key(*key))
+            .cloned()
+            .collect();
+        for key in stale {
+            if let Some(original) = self.originals.remove(&key)
+                && !original.is_empty()
+            {
+                self.queue.push((key, original));
+            }
+        }
+    }
+}
+
+/// Send the queued values to `client` in one batch request once edits settle. Before an
+/// option is first pushed, the compositor's current value is queried so reverting restores
+/// what it actually had, e.g. a value set with `hyprctl keyword`.
+pub fn schedule_flush(live: &Rc<RefCell<LivePreview>>, client: Option<Arc<SocketClient>>) {
+    {
+        let mut state = live.borrow_mut();
+        if state.queue.is_empty() || state.flush_scheduled {
+            return;
+        }
+        state.flush_scheduled = true;
+    }
+
+    let live = live.clone();
+    glib::timeout_add_local_once(FLUSH_DELAY, move || {
+        let Some(client) = client else {
+            let mut state = live.borrow_mut();
+            state.flush_scheduled = false;
+            state.queue.clear();
+            return;
+        };
+        glib::MainContext::default().spawn_local(async move {
+            let (keys, generation) = {
+                let state = live.borrow();
+                (state.missing_originals(), state.generation)
+            };
+            if !keys.is_empty() {
+                let query = keys.clone();
+                let current = match ipc::call(client.clone(), move |c| c.getoptions(&query)).await {
+                    Ok(options) => options
+                        .iter()
+                        .filter_map(|o| Some((o.option.clone(), o.to_config_string()?)))
+                        .collect(),
+                    Err(e) => {
+                        eprintln!("Live preview: {}", e);
+                        HashMap::new()
+                    }
+                };
+                let mut state = live.borrow_mut();
+                if state.generation == generation {
+                    state.settle(keys, current);
+                }
+            }
+
+            let queue = std::mem::take(&mut live.borrow_mut().queue);
+            if !queue.is_empty() {
+                report(ipc::call(client.clone(), move |c| c.keywords(&queue)).await);
+            }
+            live.borrow_mut().flush_scheduled = false;
+            schedule_flush(&live, Some(client));
+        });
+    });
+}
+
+/// Send the queued values to `client` right away, e.g. when the window is about to close.
+/// The queue is taken on the call, so switching instances before awaiting the result
+/// still sends them to the instance they were meant for.
+pub fn flush_now(
+    live: &Rc<RefCell<LivePreview>>,
+    client: Option<Arc<SocketClient>>,
+) -> impl Future<Output = ()> + use<> {
+    let queue = std::mem::take(&mut live.borrow_mut().queue);
+    async move {
+        if let Some(client) = client
+            && !queue.is_empty()
+        {
+            report(ipc::call(client, move |c| c.keywords(&queue)).await);
+        }
+    }
+}
+
+fn report(result: io::Result<Vec<String>>) {
+    match result {
+        Ok(errors) => {
+            for error in errors {
+                eprintln!("Live preview: {}", error);
+            }
+        }
+        Err(e) => eprintln!("Live preview: {}", e),
+    }
+}
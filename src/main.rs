@@ -1,27 +1,73 @@
-use gtk::{Application, Button, prelude::*};
+use gtk::{Application, Button, glib, prelude::*};
 use hyprparser::parse_config;
+use ipc::HyprlandRequests;
+use std::os::unix::fs::MetadataExt;
 use std::{cell::RefCell, env, fs, path::Path, path::PathBuf, rc::Rc};
 
+mod backups;
+mod config_errors;
+mod config_lines;
+mod defaults;
+mod diff;
+mod drm;
 mod gui;
+mod history;
+mod ipc;
+mod keybinds;
+mod live;
 mod modules;
+mod monitor_canvas;
+mod monitors;
+mod monitorv2;
+mod profiles;
+mod rule_fields;
+mod runtime;
+mod scales;
+mod settings;
 mod widgets;
 
-const CONFIG_PATH: &str = ".config/hypr/hyprland.conf";
-const BACKUP_SUFFIX: &str = "-bak";
+/// Written by the first-run setup when no config exists yet.
+const STARTER_CONFIG: &str = include_str!("templates/hyprland.conf");
 
 fn main() {
     let app = Application::builder()
         .application_id("nnyyxxxx.hyprgui")
         .build();
 
-    app.connect_activate(build_ui);
+    app.add_main_option(
+        "config",
+        glib::Char::from(b'c'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::Filename,
+        "Edit this hyprland.conf instead of the detected one",
+        Some("PATH"),
+    );
+    let cli_path: Rc<RefCell<Option<PathBuf>>> = Rc::new(RefCell::new(None));
+    let cli_path_clone = cli_path.clone();
+    app.connect_handle_local_options(move |_, options| {
+        if let Ok(Some(path)) = options.lookup::<PathBuf>("config") {
+            let path = env::current_dir()
+                .map(|cwd| cwd.join(&path))
+                .unwrap_or(path);
+            *cli_path_clone.borrow_mut() = Some(path);
+        }
+        std::ops::ControlFlow::Continue(())
+    });
+
+    app.connect_activate(move |app| build_ui(app, resolve_config_path(cli_path.borrow().clone())));
     app.run();
 }
 
-fn build_ui(app: &Application) {
+fn build_ui(app: &Application, config_path: PathBuf) {
     // Inject a tiny CSS provider to avoid GTK slider min-size warnings without touching system theme
     let provider = gtk::CssProvider::new();
-    provider.load_from_data("slider { min-width: 1px; min-height: 1px; }");
+    provider.load_from_data(
+        "slider { min-width: 1px; min-height: 1px; }
+        .modified { background-color: alpha(@theme_selected_bg_color, 0.15); border-radius: 6px; }
+        .conflict { background-color: alpha(@error_color, 0.15); }
+        .config-error { background-color: alpha(@error_color, 0.25); border-radius: 6px; }
+        .runtime-value { opacity: 0.6; }",
+    );
     if let Some(display) = gtk::gdk::Display::default() {
         gtk::style_context_add_provider_for_display(
             &display,
@@ -30,72 +76,116 @@ fn build_ui(app: &Application) {
         );
     }
 
-    let gui = Rc::new(RefCell::new(gui::ConfigGUI::new(app)));
+    let gui = Rc::new(RefCell::new(gui::ConfigGUI::new(app, config_path.clone())));
     gui::ConfigGUI::setup_config_buttons(gui.clone());
 
-    let config_path_full = get_config_path();
+    let gui_clone = gui.clone();
+    gui.borrow().save_button.connect_clicked(move |button| {
+        if let Some(popover) = button.ancestor(gtk::Popover::static_type())
+            && let Some(popover) = popover.downcast_ref::<gtk::Popover>()
+        {
+            popover.popdown();
+        }
+        save_config_file(gui_clone.clone(), false);
+    });
 
-    if !config_path_full.exists() {
-        gui.borrow_mut().custom_error_popup_critical(
-            "File not found",
-            &format!("File not found: ~/{}", CONFIG_PATH),
-            true,
-        );
-    } else {
-        let config_str = match fs::read_to_string(&config_path_full) {
-            Ok(s) => s,
-            Err(e) => {
-                gui.borrow_mut().custom_error_popup_critical(
-                    "Reading failed",
-                    &format!("Failed to read the configuration file: {}", e),
-                    true,
-                );
-                String::new()
-            }
-        };
-        let parsed_config = parse_config(&config_str);
-        gui.borrow_mut().load_config(&parsed_config);
+    setup_history_actions(app, gui.clone());
+    setup_close_guard(gui.clone());
+    setup_file_watch(gui.clone());
+    setup_live_preview(gui.clone());
+    setup_instance_picker(gui.clone());
+    setup_profile_actions(gui.clone());
 
-        let gui_clone = gui.clone();
-        gui.borrow().save_button.connect_clicked(move |button| {
-            if let Some(popover) = button.ancestor(gtk::Popover::static_type())
-                && let Some(popover) = popover.downcast_ref::<gtk::Popover>()
-            {
-                popover.popdown();
-            }
-            save_config_file(gui_clone.clone());
-        });
+    let gui_clone = gui.clone();
+    gui.borrow().search_entry.connect_changed(move |entry| {
+        filter_options(gui_clone.clone(), entry.text());
+    });
 
-        let gui_clone = gui.clone();
-        gui.borrow().search_entry.connect_changed(move |entry| {
-            filter_options(gui_clone.clone(), entry.text());
-        });
+    let open_button = Button::with_label("Open Config File…");
+    let revert_live_button = Button::with_label("Revert Live Changes");
+    revert_live_button.set_action_name(Some("win.revert-live"));
+    revert_live_button.set_tooltip_text(Some(
+        "Restore the values Hyprland had before live preview changed them",
+    ));
+    let undo_button = Button::with_label("Undo Changes");
+    let backups_button = Button::with_label("Backups…");
+    let copy_button = Button::with_label("Copyright");
 
-        let undo_button = Button::with_label("Undo Changes");
-        let copy_button = Button::with_label("Copyright");
+    let gui_clone = gui.clone();
+    open_button.connect_clicked(move |button| {
+        if let Some(popover) = button.ancestor(gtk::Popover::static_type())
+            && let Some(popover) = popover.downcast_ref::<gtk::Popover>()
+        {
+            popover.popdown();
+        }
 
-        let gui_clone = gui.clone();
-        undo_button.connect_clicked(move |button| {
-            if let Some(popover) = button.ancestor(gtk::Popover::static_type())
-                && let Some(popover) = popover.downcast_ref::<gtk::Popover>()
-            {
-                popover.popdown();
-            }
+        choose_config_file(gui_clone.clone());
+    });
 
-            undo_changes(gui_clone.clone());
-        });
+    let gui_clone = gui.clone();
+    undo_button.connect_clicked(move |button| {
+        if let Some(popover) = button.ancestor(gtk::Popover::static_type())
+            && let Some(popover) = popover.downcast_ref::<gtk::Popover>()
+        {
+            popover.popdown();
+        }
 
-        let gui_clone = gui.clone();
-        copy_button.connect_clicked(move |button| {
-            if let Some(popover) = button.ancestor(gtk::Popover::static_type())
-                && let Some(popover) = popover.downcast_ref::<gtk::Popover>()
-            {
-                popover.popdown();
-            }
+        undo_changes(gui_clone.clone());
+    });
 
-            gui_clone.borrow_mut().custom_info_popup(
-                "Copyright (C) 2024 HyprUtils",
-                "This program is free software; you can redistribute it and/or
+    let gui_clone = gui.clone();
+    backups_button.connect_clicked(move |button| {
+        if let Some(popover) = button.ancestor(gtk::Popover::static_type())
+            && let Some(popover) = popover.downcast_ref::<gtk::Popover>()
+        {
+            popover.popdown();
+        }
+
+        show_backups_dialog(gui_clone.clone());
+    });
+
+    let backup_limit_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+    let backup_limit_label = gtk::Label::new(Some("Backups to keep"));
+    backup_limit_label.set_hexpand(true);
+    backup_limit_label.set_halign(gtk::Align::Start);
+    let backup_limit_spin = gtk::SpinButton::with_range(1.0, 100.0, 1.0);
+    backup_limit_spin.set_value(gui.borrow().settings.borrow().backup_limit as f64);
+    let settings = gui.borrow().settings.clone();
+    backup_limit_spin.connect_value_changed(move |spin| {
+        let mut settings = settings.borrow_mut();
+        settings.backup_limit = spin.value() as usize;
+        if let Err(e) = settings.save() {
+            eprintln!("Failed to save HyprGUI settings: {}", e);
+        }
+    });
+    backup_limit_box.append(&backup_limit_label);
+    backup_limit_box.append(&backup_limit_spin);
+
+    let reload_check = gtk::CheckButton::with_label("Reload Hyprland after saving");
+    reload_check.set_tooltip_text(Some(
+        "Ask Hyprland to reload after each save and show the config errors it reports",
+    ));
+    reload_check.set_active(gui.borrow().settings.borrow().reload_after_save);
+    let settings = gui.borrow().settings.clone();
+    reload_check.connect_toggled(move |check| {
+        let mut settings = settings.borrow_mut();
+        settings.reload_after_save = check.is_active();
+        if let Err(e) = settings.save() {
+            eprintln!("Failed to save HyprGUI settings: {}", e);
+        }
+    });
+
+    let gui_clone = gui.clone();
+    copy_button.connect_clicked(move |button| {
+        if let Some(popover) = button.ancestor(gtk::Popover::static_type())
+            && let Some(popover) = popover.downcast_ref::<gtk::Popover>()
+        {
+            popover.popdown();
+        }
+
+        gui_clone.borrow_mut().custom_info_popup(
+            "Copyright (C) 2024 HyprUtils",
+            "This program is free software; you can redistribute it and/or
 modify it under the terms of the GNU General Public License
 as published by the Free Software Foundation, version 2 of
 the License.
@@ -106,19 +196,610 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program; if not, see
 <https://www.gnu.org/licenses/old-licenses/gpl-2.0>.",
+            true,
+        );
+    });
+
+    if let Some(gear_menu_box) = gui.borrow().gear_menu.borrow().child()
+        && let Some(box_widget) = gear_menu_box.downcast_ref::<gtk::Box>()
+    {
+        box_widget.append(&open_button);
+        box_widget.append(&revert_live_button);
+        box_widget.append(&undo_button);
+        box_widget.append(&backups_button);
+        box_widget.append(&backup_limit_box);
+        box_widget.append(&reload_check);
+        box_widget.append(&copy_button);
+    }
+
+    gui.borrow().window.present();
+
+    // The pages show the defaults of the running Hyprland, so load once it answered
+    let client = gui.borrow().client.clone();
+    glib::MainContext::default().spawn_local(async move {
+        let defaults = defaults::Defaults::load(client).await;
+        gui.borrow_mut().set_defaults(defaults);
+        match fs::read_to_string(&config_path) {
+            Ok(config_str) => {
+                let parsed_config = parse_config(&config_str);
+                gui.borrow_mut().load_config(&parsed_config);
+                gui.borrow_mut().watch_files(&config_path, &parsed_config);
+            }
+            Err(e) => show_setup_dialog(gui, e),
+        }
+    });
+}
+
+/// Offer ways forward when the config can't be read, instead of giving up.
+fn show_setup_dialog(gui: Rc<RefCell<gui::ConfigGUI>>, error: std::io::Error) {
+    let path = gui.borrow().config_path.clone();
+    let missing = error.kind() == std::io::ErrorKind::NotFound;
+
+    let dialog = gtk::MessageDialog::builder()
+        .message_type(gtk::MessageType::Question)
+        .title("Set Up Configuration")
+        .text(if missing {
+            "No Hyprland configuration found"
+        } else {
+            "The Hyprland configuration can't be read"
+        })
+        .secondary_text(if missing {
+            format!(
+                "{} does not exist. Choose another file, create a starter config, or start \
+                 empty and let the first save create it.",
+                path.display()
+            )
+        } else {
+            format!("Failed to read {}: {}", path.display(), error)
+        })
+        .transient_for(&gui.borrow().window)
+        .modal(true)
+        .build();
+    dialog.add_button("Quit", gtk::ResponseType::Close);
+    dialog.add_button("Choose File…", gtk::ResponseType::Other(0));
+    if missing {
+        dialog.add_button("Start Empty", gtk::ResponseType::Other(1));
+        dialog.add_button("Create Starter Config", gtk::ResponseType::Accept);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+    }
+
+    dialog.connect_response(move |dialog, response| {
+        dialog.close();
+        match response {
+            gtk::ResponseType::Other(0) => choose_config_file(gui.clone()),
+            gtk::ResponseType::Other(1) => {
+                let mut gui_ref = gui.borrow_mut();
+                let parsed_config = parse_config("");
+                gui_ref.load_config(&parsed_config);
+                gui_ref.watch_files(&path, &parsed_config);
+            }
+            gtk::ResponseType::Accept => create_starter_config(gui.clone()),
+            _ => gui.borrow().window.close(),
+        }
+    });
+    dialog.present();
+}
+
+fn create_starter_config(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let path = gui.borrow().config_path.clone();
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, STARTER_CONFIG));
+    match result {
+        Ok(()) => open_config_file(gui, path),
+        Err(e) => {
+            gui.borrow_mut().custom_error_popup(
+                "Creating failed",
+                &format!("Failed to create {}: {}", path.display(), e),
                 true,
             );
-        });
+            show_setup_dialog(gui, e);
+        }
+    }
+}
+
+fn setup_history_actions(app: &Application, gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let undo_action = gtk::gio::SimpleAction::new("undo", None);
+    let gui_clone = gui.clone();
+    undo_action.connect_activate(move |_, _| gui_clone.borrow().undo());
+
+    let redo_action = gtk::gio::SimpleAction::new("redo", None);
+    let gui_clone = gui.clone();
+    redo_action.connect_activate(move |_, _| gui_clone.borrow().redo());
+
+    let window = gui.borrow().window.clone();
+    window.add_action(&undo_action);
+    window.add_action(&redo_action);
+    app.set_accels_for_action("win.undo", &["<Control>z"]);
+    app.set_accels_for_action("win.redo", &["<Control><Shift>z", "<Control>y"]);
+}
 
-        if let Some(gear_menu_box) = gui.borrow().gear_menu.borrow().child()
-            && let Some(box_widget) = gear_menu_box.downcast_ref::<gtk::Box>()
+fn setup_live_preview(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let enabled = gui.borrow().settings.borrow().live_preview;
+    gui.borrow().live.borrow_mut().set_enabled(enabled);
+
+    let live_action = gtk::gio::SimpleAction::new_stateful("live-preview", None, &enabled.into());
+    let gui_clone = gui.clone();
+    live_action.connect_change_state(move |action, state| {
+        let Some(enabled) = state.and_then(|s| s.get::<bool>()) else {
+            return;
+        };
+        action.set_state(&enabled.into());
+        let gui_ref = gui_clone.borrow();
+        gui_ref.live.borrow_mut().set_enabled(enabled);
         {
-            box_widget.append(&undo_button);
-            box_widget.append(&copy_button);
+            let mut settings = gui_ref.settings.borrow_mut();
+            settings.live_preview = enabled;
+            if let Err(e) = settings.save() {
+                eprintln!("Failed to save HyprGUI settings: {}", e);
+            }
         }
+        gui_ref.sync_live_preview();
+    });
+
+    let revert_action = gtk::gio::SimpleAction::new("revert-live", None);
+    let gui_clone = gui.clone();
+    let live_action_clone = live_action.clone();
+    revert_action.connect_activate(move |_, _| {
+        // Turn preview off first, or the next edit would push everything again
+        live_action_clone.change_state(&false.into());
+        gui_clone.borrow().revert_live_preview();
+    });
+
+    let window = gui.borrow().window.clone();
+    window.add_action(&live_action);
+    window.add_action(&revert_action);
+}
+
+/// How often the instance picker looks for Hyprland instances started or stopped.
+const INSTANCE_REFRESH_SECONDS: u32 = 3;
+
+/// Fill the instance picker, keep it current and route compositor requests to the chosen
+/// instance.
+fn setup_instance_picker(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let dropdown = gui.borrow().instance_dropdown.clone();
+    let targets: Rc<RefCell<Vec<ipc::Target>>> = Rc::new(RefCell::new(Vec::new()));
+    let filling = Rc::new(std::cell::Cell::new(false));
+    let current = ipc::Target::of(gui.borrow().client.as_ref());
+    fill_instances(&dropdown, &targets, &filling, &current);
+    set_runtime_enabled(&gui, current != ipc::Target::Offline);
+
+    let gui_for_refresh = gui.clone();
+    let listener = Rc::new(RefCell::new(listen_for_hotplug(gui.clone())));
+    let targets_clone = targets.clone();
+    let filling_clone = filling.clone();
+    dropdown.connect_selected_notify(move |dd| {
+        if filling_clone.get() {
+            return;
+        }
+        let Some(target) = targets_clone.borrow().get(dd.selected() as usize).cloned() else {
+            return;
+        };
+        if target == ipc::Target::of(gui.borrow().client.as_ref()) {
+            return;
+        }
+        if let Some(handle) = listener.borrow_mut().take() {
+            handle.abort();
+        }
+        switch_instance(gui.clone(), target);
+        *listener.borrow_mut() = listen_for_hotplug(gui.clone());
+    });
+
+    let dropdown = dropdown.downgrade();
+    let gui = Rc::downgrade(&gui_for_refresh);
+    glib::timeout_add_seconds_local(INSTANCE_REFRESH_SECONDS, move || {
+        let (Some(dropdown), Some(gui)) = (dropdown.upgrade(), gui.upgrade()) else {
+            return glib::ControlFlow::Break;
+        };
+        let current = ipc::Target::of(gui.borrow().client.as_ref());
+        fill_instances(&dropdown, &targets, &filling, &current);
+        glib::ControlFlow::Continue
+    });
+}
+
+/// Offer the running instances plus "Offline" when they changed, keeping the `current`
+/// target selected. When it stopped running, "Offline" is selected, which switches to it.
+fn fill_instances(
+    dropdown: &gtk::DropDown,
+    targets: &Rc<RefCell<Vec<ipc::Target>>>,
+    filling: &Rc<std::cell::Cell<bool>>,
+    current: &ipc::Target,
+) {
+    let mut found: Vec<ipc::Target> = Vec::new();
+    let mut labels: Vec<String> = Vec::new();
+    for instance in ipc::instances() {
+        labels.push(instance.label());
+        found.push(ipc::Target::Instance(instance.signature));
+    }
+    found.push(ipc::Target::Offline);
+    labels.push("Offline".to_string());
+    if *targets.borrow() == found {
+        return;
     }
 
-    gui.borrow().window.present();
+    let selected = found
+        .iter()
+        .position(|t| t == current)
+        .unwrap_or(found.len() - 1);
+    *targets.borrow_mut() = found;
+    let refs: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+    filling.set(true);
+    dropdown.set_model(Some(&gtk::StringList::new(&refs)));
+    filling.set(false);
+    dropdown.set_selected(selected as u32);
+}
+
+/// Point every compositor request at `target` and reload what came from the old one.
+fn switch_instance(gui: Rc<RefCell<gui::ConfigGUI>>, target: ipc::Target) {
+    // Put back what live preview changed on the instance being left
+    let live = gui.borrow().live.clone();
+    live.borrow_mut().revert();
+    let reverted = live::flush_now(&live, gui.borrow().client.clone());
+
+    let client = target.client();
+    gui.borrow_mut().client = client.clone();
+    set_runtime_enabled(&gui, client.is_some());
+    glib::MainContext::default().spawn_local(async move {
+        reverted.await;
+        let defaults = defaults::Defaults::load(client).await;
+        gui.borrow_mut().set_defaults(defaults);
+        reload_external_changes(gui);
+    });
+}
+
+/// Turn live preview off and make it unavailable while no compositor is chosen.
+fn set_runtime_enabled(gui: &Rc<RefCell<gui::ConfigGUI>>, enabled: bool) {
+    let window = gui.borrow().window.clone();
+    let Some(action) = window
+        .lookup_action("live-preview")
+        .and_downcast::<gtk::gio::SimpleAction>()
+    else {
+        return;
+    };
+    if !enabled {
+        action.change_state(&false.into());
+    }
+    action.set_enabled(enabled);
+}
+
+/// Refresh the Monitors page dropdowns when outputs are plugged in or removed.
+fn listen_for_hotplug(gui: Rc<RefCell<gui::ConfigGUI>>) -> Option<glib::JoinHandle<()>> {
+    let client = gui.borrow().client.clone()?;
+    let path = client.event_socket();
+    // Docking usually reports several outputs at once, so refresh once they settle
+    let scheduled = Rc::new(std::cell::Cell::new(false));
+    let handler = move |event: ipc::Event| {
+        if !matches!(
+            event.name.as_str(),
+            "monitoradded" | "monitorremoved" | "monitoraddedv2" | "monitorremovedv2"
+        ) || scheduled.replace(true)
+        {
+            return;
+        }
+        let gui = gui.clone();
+        let client = client.clone();
+        let scheduled = scheduled.clone();
+        glib::timeout_add_local_once(std::time::Duration::from_millis(250), move || {
+            scheduled.set(false);
+            glib::MainContext::default().spawn_local(async move {
+                let monitors = match ipc::call(client, |c| c.monitors()).await {
+                    Ok(monitors) => monitors,
+                    Err(e) => {
+                        eprintln!("Failed to query monitors: {}", e);
+                        return;
+                    }
+                };
+                // The page may have been rebuilt while the query ran
+                let Some(tables) = gui.try_borrow().ok().and_then(|g| {
+                    g.config_widgets
+                        .get("monitors")
+                        .map(|w| w.rule_tables.clone())
+                }) else {
+                    return;
+                };
+                let data = monitors::MonitorData::from_monitors(&monitors, &tables.monitor_rules());
+                tables.refresh_monitors(data);
+            });
+        });
+    };
+    Some(glib::MainContext::default().spawn_local(async move {
+        if let Err(e) = ipc::listen_events(path, handler).await {
+            eprintln!("Failed to listen for Hyprland events: {}", e);
+        }
+    }))
+}
+
+/// Actions behind the profile controls on the Monitors page.
+fn setup_profile_actions(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let activate_action =
+        gtk::gio::SimpleAction::new("activate-profile", Some(glib::VariantTy::STRING));
+    let gui_clone = gui.clone();
+    activate_action.connect_activate(move |_, parameter| {
+        if let Some(name) = parameter.and_then(|p| p.get::<String>()) {
+            activate_profile(gui_clone.clone(), &name);
+        }
+    });
+
+    let save_action = gtk::gio::SimpleAction::new("save-profile", None);
+    let gui_clone = gui.clone();
+    save_action.connect_activate(move |_, _| show_save_profile_dialog(gui_clone.clone()));
+
+    let delete_action =
+        gtk::gio::SimpleAction::new("delete-profile", Some(glib::VariantTy::STRING));
+    let gui_clone = gui.clone();
+    delete_action.connect_activate(move |_, parameter| {
+        if let Some(name) = parameter.and_then(|p| p.get::<String>()) {
+            delete_profile(gui_clone.clone(), name);
+        }
+    });
+
+    let window = gui.borrow().window.clone();
+    window.add_action(&activate_action);
+    window.add_action(&save_action);
+    window.add_action(&delete_action);
+}
+
+/// Write a stored profile to the profile file, sourcing it from the main config on first
+/// use. Goes through the diff dialog like a save, so Hyprland reloads it afterwards.
+fn activate_profile(gui: Rc<RefCell<gui::ConfigGUI>>, name: &str) {
+    let mut gui_ref = gui.borrow_mut();
+    if !gui_ref.get_changes().borrow().is_empty() {
+        gui_ref.custom_error_popup(
+            "Unsaved Changes",
+            "Save or undo your pending changes before switching profiles.",
+            true,
+        );
+        return;
+    }
+
+    let path = gui_ref.config_path.clone();
+    let profile_path = profiles::profile_path(&path);
+    let mut stored = profiles::Profiles::load();
+    // Keep edits made to the file of the profile being left
+    if let Some(active) = profiles::active_profile(&profile_path)
+        && stored.get(&active).is_some()
+        && let Ok(content) = fs::read_to_string(&profile_path)
+    {
+        let (monitors, workspaces) = profiles::read_rules(&content);
+        stored.insert(profiles::Profile {
+            name: active,
+            monitors,
+            workspaces,
+        });
+        if let Err(e) = stored.save() {
+            eprintln!("Failed to save monitor profiles: {}", e);
+        }
+    }
+    let Some(profile) = stored.get(name).cloned() else {
+        gui_ref.custom_error_popup(
+            "Profile Missing",
+            &format!("There is no monitor profile named {}.", name),
+            true,
+        );
+        return;
+    };
+
+    let config_str = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) => {
+            gui_ref.custom_error_popup(
+                "Reading failed",
+                &format!("Failed to read the configuration file: {}", e),
+                true,
+            );
+            return;
+        }
+    };
+    let parsed_config = parse_config(&config_str);
+    let mut lines = parsed_config.content.clone();
+    if !profiles::is_sourced(&parsed_config, &profile_path) {
+        // Rules the profile was saved from move out of the main config
+        profiles::remove_rules(&mut lines, &profile);
+        lines.push(profiles::source_line(&profile_path));
+    }
+
+    let mut plan = SavePlan {
+        files: vec![path.clone(), profile_path.clone()],
+        writes: Vec::new(),
+    };
+    let updated = config_lines::join_lines(&lines, &config_str);
+    if updated != config_str {
+        plan.writes.push(PendingWrite {
+            path,
+            original: config_str,
+            updated,
+        });
+    }
+    let original = fs::read_to_string(&profile_path).unwrap_or_default();
+    let updated = profile.render();
+    if updated != original {
+        plan.writes.push(PendingWrite {
+            path: profile_path,
+            original,
+            updated,
+        });
+    }
+
+    if plan.writes.is_empty() {
+        gui_ref.custom_info_popup(
+            "Profile Active",
+            &format!("The {} profile is active already.", name),
+            true,
+        );
+        return;
+    }
+    drop(gui_ref);
+    show_diff_dialog(gui, plan, false);
+}
+
+/// Ask for a name and store the Monitors page rows as a profile under it.
+fn show_save_profile_dialog(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let gui_ref = gui.borrow();
+    let profile_path = profiles::profile_path(&gui_ref.config_path);
+    let dialog = gtk::MessageDialog::builder()
+        .message_type(gtk::MessageType::Question)
+        .title("Save Monitor Profile")
+        .text("Save the monitor and workspace rows as a profile")
+        .secondary_text("A profile with the same name is replaced. Pending edits are included.")
+        .transient_for(&gui_ref.window)
+        .modal(true)
+        .build();
+    drop(gui_ref);
+
+    let entry = gtk::Entry::new();
+    entry.set_placeholder_text(Some("docked, laptop, presentation…"));
+    if let Some(active) = profiles::active_profile(&profile_path) {
+        entry.set_text(&active);
+    }
+    entry.set_activates_default(true);
+    if let Some(area) = dialog.message_area().downcast_ref::<gtk::Box>() {
+        area.append(&entry);
+    }
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    dialog.add_button("Save", gtk::ResponseType::Accept);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    dialog.connect_response(move |dialog, response| {
+        let name = entry.text().trim().to_string();
+        dialog.close();
+        if response == gtk::ResponseType::Accept && !name.is_empty() {
+            save_profile(gui.clone(), name);
+        }
+    });
+    dialog.present();
+}
+
+/// Store the rows as profile `name`. Only the rows from the profile file and new rows
+/// belong to a profile, or before the first profile is active, the rows of the main config
+/// that activating it moves out; the rest of the config is shared by all of them.
+fn save_profile(gui: Rc<RefCell<gui::ConfigGUI>>, name: String) {
+    let mut gui_ref = gui.borrow_mut();
+    let Some(tables) = gui_ref
+        .config_widgets
+        .get("monitors")
+        .map(|w| w.rule_tables.clone())
+    else {
+        return;
+    };
+    let path = gui_ref.config_path.clone();
+    let profile_path = profiles::profile_path(&path);
+    let source = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| profiles::source_index(&parse_config(&content), &profile_path));
+    let (monitors, workspaces) = tables.rules_from(source);
+
+    let mut stored = profiles::Profiles::load();
+    stored.insert(profiles::Profile {
+        name,
+        monitors,
+        workspaces,
+    });
+    if let Err(e) = stored.save() {
+        gui_ref.custom_error_popup(
+            "Saving failed",
+            &format!("Failed to save the monitor profiles: {}", e),
+            true,
+        );
+        return;
+    }
+    if let Some(widget) = gui_ref.config_widgets.get("monitors") {
+        widget.load_profiles(&path);
+    }
+}
+
+fn delete_profile(gui: Rc<RefCell<gui::ConfigGUI>>, name: String) {
+    let dialog = gtk::MessageDialog::builder()
+        .message_type(gtk::MessageType::Question)
+        .title("Delete Monitor Profile")
+        .text(format!("Delete the {} profile?", name))
+        .secondary_text(
+            "If it is active, its file keeps the rules until another profile is activated.",
+        )
+        .transient_for(&gui.borrow().window)
+        .modal(true)
+        .build();
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    dialog.add_button("Delete", gtk::ResponseType::Accept);
+
+    dialog.connect_response(move |dialog, response| {
+        dialog.close();
+        if response != gtk::ResponseType::Accept {
+            return;
+        }
+        let mut gui_ref = gui.borrow_mut();
+        let mut stored = profiles::Profiles::load();
+        stored.remove(&name);
+        if let Err(e) = stored.save() {
+            gui_ref.custom_error_popup(
+                "Deleting failed",
+                &format!("Failed to save the monitor profiles: {}", e),
+                true,
+            );
+            return;
+        }
+        let path = gui_ref.config_path.clone();
+        if let Some(widget) = gui_ref.config_widgets.get("monitors") {
+            widget.load_profiles(&path);
+        }
+    });
+    dialog.present();
+}
+
+/// Keep the modified-state indicators current and ask before closing with pending changes.
+fn setup_close_guard(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let changes_action =
+        gtk::gio::SimpleAction::new(widgets::CHANGES_ACTION.trim_start_matches("win."), None);
+    let gui_clone = gui.clone();
+    changes_action.connect_activate(move |_, _| {
+        // Code holding the GUI refreshes it itself once done
+        if let Ok(gui_ref) = gui_clone.try_borrow() {
+            gui_ref.changes_updated();
+        }
+    });
+    let window = gui.borrow().window.clone();
+    window.add_action(&changes_action);
+
+    window.connect_close_request(move |window| {
+        if gui.borrow().get_changes().borrow().is_empty() {
+            return glib::Propagation::Proceed;
+        }
+
+        let dialog = gtk::MessageDialog::builder()
+            .message_type(gtk::MessageType::Question)
+            .title("Unsaved Changes")
+            .text("Save your changes before closing?")
+            .secondary_text("Changes that are not saved will be lost.")
+            .transient_for(window)
+            .modal(true)
+            .build();
+        dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+        dialog.add_button("Discard", gtk::ResponseType::Reject);
+        dialog.add_button("Save", gtk::ResponseType::Accept);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+
+        let gui = gui.clone();
+        dialog.connect_response(move |dialog, response| {
+            dialog.close();
+            match response {
+                gtk::ResponseType::Accept => save_config_file(gui.clone(), true),
+                gtk::ResponseType::Reject => {
+                    let window = gui.borrow().window.clone();
+                    let live = gui.borrow().live.clone();
+                    let client = gui.borrow().client.clone();
+                    gui.borrow().get_changes().borrow_mut().clear();
+                    live.borrow_mut().revert();
+                    glib::MainContext::default().spawn_local(async move {
+                        live::flush_now(&live, client).await;
+                        window.close();
+                    });
+                }
+                _ => {}
+            }
+        });
+        dialog.present();
+        glib::Propagation::Stop
+    });
 }
 
 fn filter_options(gui: Rc<RefCell<gui::ConfigGUI>>, search_text: impl AsRef<str>) {
@@ -166,19 +847,120 @@ fn filter_options(gui: Rc<RefCell<gui::ConfigGUI>>, search_text: impl AsRef<str>
     }
 }
 
-fn save_config_file(gui: Rc<RefCell<gui::ConfigGUI>>) {
+/// Offer to reload when a watched config file is edited outside HyprGUI.
+fn setup_file_watch(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let prompt_open = Rc::new(std::cell::Cell::new(false));
+    let action =
+        gtk::gio::SimpleAction::new(gui::EXTERNAL_CHANGE_ACTION.trim_start_matches("win."), None);
+    let gui_clone = gui.clone();
+    action.connect_activate(move |_, _| {
+        if prompt_open.get() {
+            return;
+        }
+        let Ok(gui_ref) = gui_clone.try_borrow() else {
+            return;
+        };
+        // Our own saves update the snapshot first, so they never get here
+        let files = gui_ref.externally_changed_files();
+        if files.is_empty() {
+            return;
+        }
+        let has_changes = !gui_ref.get_changes().borrow().is_empty();
+        let file_list: Vec<String> = files.iter().map(|p| p.display().to_string()).collect();
+
+        let dialog = gtk::MessageDialog::builder()
+            .message_type(gtk::MessageType::Question)
+            .title("Files Changed on Disk")
+            .text("The configuration was changed outside HyprGUI. Reload it?")
+            .secondary_text(format!(
+                "{}\n\n{}",
+                file_list.join("\n"),
+                if has_changes {
+                    "Your pending changes are kept. Options that were changed in both places are flagged."
+                } else {
+                    "You have no pending changes."
+                }
+            ))
+            .transient_for(&gui_ref.window)
+            .modal(true)
+            .build();
+        drop(gui_ref);
+        dialog.add_button("Ignore", gtk::ResponseType::Reject);
+        dialog.add_button("Reload", gtk::ResponseType::Accept);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+        prompt_open.set(true);
+
+        let gui = gui_clone.clone();
+        let prompt_open = prompt_open.clone();
+        dialog.connect_response(move |dialog, response| {
+            dialog.close();
+            prompt_open.set(false);
+            if response == gtk::ResponseType::Accept {
+                reload_external_changes(gui.clone());
+            } else {
+                gui.borrow_mut().acknowledge_file_changes();
+            }
+        });
+        dialog.present();
+    });
+    gui.borrow().window.add_action(&action);
+}
+
+fn reload_external_changes(gui: Rc<RefCell<gui::ConfigGUI>>) {
     let mut gui_ref = gui.borrow_mut();
-    let path = get_config_path();
-    let backup_path = path.with_file_name(format!(
-        "{}{}",
-        path.file_name().unwrap().to_str().unwrap(),
-        BACKUP_SUFFIX
-    ));
+    let path = gui_ref.config_path.clone();
+    let config_str = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) => {
+            gui_ref.custom_error_popup(
+                "Reading failed",
+                &format!("Failed to read the configuration file: {}", e),
+                true,
+            );
+            return;
+        }
+    };
+
+    let parsed_config = parse_config(&config_str);
+    let conflicts = gui_ref.reload_keeping_changes(&parsed_config);
+    gui_ref.watch_files(&path, &parsed_config);
+
+    if conflicts > 0 {
+        gui_ref.custom_info_popup(
+            "Configuration Reloaded",
+            &format!(
+                "{} edit(s) you made were also changed on disk and are highlighted.",
+                conflicts
+            ),
+            true,
+        );
+    }
+}
+
+/// A file whose content changes on save.
+struct PendingWrite {
+    path: PathBuf,
+    original: String,
+    updated: String,
+}
+
+/// Everything a save writes, plus every config file to back up before writing.
+struct SavePlan {
+    files: Vec<PathBuf>,
+    writes: Vec<PendingWrite>,
+}
+
+/// Save pending changes after showing a diff; `close_after` closes the window once saved.
+fn save_config_file(gui: Rc<RefCell<gui::ConfigGUI>>, close_after: bool) {
+    let mut gui_ref = gui.borrow_mut();
+    let path = gui_ref.config_path.clone();
 
     let config_str = match fs::read_to_string(&path) {
         Ok(s) => s,
+        // Started empty during setup: the first save creates the file
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
         Err(e) => {
-            gui_ref.custom_error_popup_critical(
+            gui_ref.custom_error_popup(
                 "Reading failed",
                 &format!("Failed to read the configuration file: {}", e),
                 true,
@@ -190,155 +972,561 @@ fn save_config_file(gui: Rc<RefCell<gui::ConfigGUI>>) {
     let mut parsed_config = parse_config(&config_str);
     let changes = gui_ref.get_changes();
 
-    if !changes.borrow().is_empty() {
-        if !backup_path.exists() {
-            if let Err(e) = fs::copy(&path, &backup_path) {
-                gui_ref.custom_error_popup(
-                    "Backup failed",
-                    &format!("Failed to create backup: {}", e),
-                    true,
-                );
-                return;
-            }
+    if changes.borrow().is_empty() {
+        gui_ref.custom_info_popup("Saving failed", "No changes to save.", true);
+        return;
+    }
 
-            for sourced_path in &parsed_config.sourced_paths {
-                let sourced_backup = Path::new(sourced_path).with_file_name(format!(
-                    "{}{}",
-                    Path::new(sourced_path)
-                        .file_name()
-                        .unwrap()
-                        .to_str()
-                        .unwrap(),
-                    BACKUP_SUFFIX
-                ));
-                if let Err(e) = fs::copy(sourced_path, &sourced_backup) {
-                    gui_ref.custom_error_popup(
-                        "Backup failed",
-                        &format!("Failed to create backup for sourced file: {}", e),
-                        true,
-                    );
-                    return;
-                }
-            }
+    // Monitor layouts are only written once they were applied and kept, unless there's
+    // no compositor to try them on
+    if gui_ref.client.is_some()
+        && gui_ref
+            .config_widgets
+            .get("monitors")
+            .is_some_and(|widget| !widget.rule_tables.is_confirmed())
+    {
+        gui_ref.custom_error_popup(
+            "Saving failed",
+            "The monitor layout was changed but not confirmed. Use \"Apply now\" on the Monitors page and keep the layout before saving.",
+            true,
+        );
+        return;
+    }
+
+    gui_ref.apply_changes(&mut parsed_config);
+
+    let mut plan = SavePlan {
+        files: vec![path.clone()],
+        writes: Vec::new(),
+    };
+    // A file created by this save gets a trailing newline
+    let newline_hint = if path.exists() {
+        config_str.as_str()
+    } else {
+        "\n"
+    };
+    let updated_config_str = config_lines::join_lines(&parsed_config.content, newline_hint);
+    if updated_config_str != config_str {
+        plan.writes.push(PendingWrite {
+            path: path.clone(),
+            original: config_str,
+            updated: updated_config_str,
+        });
+    }
+    for (idx, sourced_path) in parsed_config.sourced_paths.iter().enumerate() {
+        plan.files.push(PathBuf::from(sourced_path));
+        let Some(lines) = parsed_config.sourced_content.get(idx) else {
+            continue;
+        };
+        let original = fs::read_to_string(sourced_path).unwrap_or_default();
+        let updated = config_lines::join_lines(lines, &original);
+        if updated != original {
+            plan.writes.push(PendingWrite {
+                path: PathBuf::from(sourced_path),
+                original,
+                updated,
+            });
         }
+    }
+
+    if plan.writes.is_empty() && close_after {
+        changes.borrow_mut().clear();
+        let window = gui_ref.window.clone();
+        drop(gui_ref);
+        window.close();
+        return;
+    }
+    if plan.writes.is_empty() {
+        gui_ref.custom_info_popup(
+            "Saving failed",
+            "The changes match the files on disk already.",
+            true,
+        );
+        return;
+    }
+
+    drop(gui_ref);
+    show_diff_dialog(gui, plan, close_after);
+}
 
-        gui_ref.apply_changes(&mut parsed_config);
+fn show_diff_dialog(gui: Rc<RefCell<gui::ConfigGUI>>, plan: SavePlan, close_after: bool) {
+    let dialog = gtk::Window::builder()
+        .title("Review Changes")
+        .modal(true)
+        .transient_for(&gui.borrow().window)
+        .default_width(800)
+        .default_height(600)
+        .build();
 
-        let updated_config_str = parsed_config.to_string();
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    vbox.set_margin_top(10);
+    vbox.set_margin_bottom(10);
+    vbox.set_margin_start(10);
+    vbox.set_margin_end(10);
 
-        match fs::write(&path, updated_config_str) {
-            Ok(_) => println!("Configuration saved successfully"),
-            Err(e) => {
-                gui_ref.custom_error_popup(
-                    "Saving failed",
-                    &format!("Failed to save the configuration: {}", e),
-                    true,
-                );
+    let buffer = gtk::TextBuffer::new(None);
+    let file_tag = buffer.create_tag(Some("file"), &[("weight", &700)]);
+    let hunk_tag = buffer.create_tag(Some("hunk"), &[("foreground", &"#1c71d8")]);
+    let added_tag = buffer.create_tag(Some("added"), &[("foreground", &"#26a269")]);
+    let removed_tag = buffer.create_tag(Some("removed"), &[("foreground", &"#c01c28")]);
+    for write in &plan.writes {
+        let patch = diff::unified_diff(
+            &write.original,
+            &write.updated,
+            &write.path.display().to_string(),
+            3,
+        );
+        for line in patch.lines() {
+            let tag = if line.starts_with("---") || line.starts_with("+++") {
+                file_tag.as_ref()
+            } else if line.starts_with("@@") {
+                hunk_tag.as_ref()
+            } else if line.starts_with('+') {
+                added_tag.as_ref()
+            } else if line.starts_with('-') {
+                removed_tag.as_ref()
+            } else {
+                None
+            };
+            let mut end = buffer.end_iter();
+            match tag {
+                Some(tag) => buffer.insert_with_tags(&mut end, &format!("{}\n", line), &[tag]),
+                None => buffer.insert(&mut end, &format!("{}\n", line)),
             }
         }
-    } else {
-        gui_ref.custom_info_popup("Saving failed", "No changes to save.", true);
+        let mut end = buffer.end_iter();
+        buffer.insert(&mut end, "\n");
     }
+
+    let text_view = gtk::TextView::with_buffer(&buffer);
+    text_view.set_editable(false);
+    text_view.set_cursor_visible(false);
+    text_view.set_monospace(true);
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_vexpand(true);
+    scrolled.set_child(Some(&text_view));
+    vbox.append(&scrolled);
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    buttons.set_halign(gtk::Align::End);
+    let cancel_button = Button::with_label("Cancel");
+    let confirm_button = Button::with_label("Confirm");
+    confirm_button.add_css_class("suggested-action");
+    buttons.append(&cancel_button);
+    buttons.append(&confirm_button);
+    vbox.append(&buttons);
+
+    let dialog_clone = dialog.clone();
+    cancel_button.connect_clicked(move |_| dialog_clone.close());
+
+    let dialog_clone = dialog.clone();
+    let plan = RefCell::new(Some(plan));
+    confirm_button.connect_clicked(move |_| {
+        dialog_clone.close();
+        if let Some(plan) = plan.borrow_mut().take() {
+            write_save_plan(gui.clone(), plan, close_after);
+        }
+    });
+
+    dialog.set_child(Some(&vbox));
+    dialog.present();
 }
 
-fn undo_changes(gui: Rc<RefCell<gui::ConfigGUI>>) {
+fn write_save_plan(gui: Rc<RefCell<gui::ConfigGUI>>, plan: SavePlan, close_after: bool) {
     let mut gui_ref = gui.borrow_mut();
-    let path = get_config_path();
-    let backup_path = path.with_file_name(format!(
-        "{}{}",
-        path.file_name().unwrap().to_str().unwrap(),
-        BACKUP_SUFFIX
-    ));
 
-    if backup_path.exists() {
-        let config_str = match fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(e) => {
-                gui_ref.custom_error_popup(
-                    "Reading failed",
-                    &format!("Failed to read configuration: {}", e),
-                    true,
-                );
-                return;
-            }
-        };
+    let backup_limit = gui_ref.settings.borrow().backup_limit;
+    if let Err(e) = backups::create(&plan.files, backup_limit, false) {
+        gui_ref.custom_error_popup(
+            "Backup failed",
+            &format!("Failed to create backup: {}", e),
+            true,
+        );
+        return;
+    }
 
+    for write in &plan.writes {
+        if let Some(parent) = write.path.parent()
+            && let Err(e) = fs::create_dir_all(parent)
+        {
+            gui_ref.custom_error_popup(
+                "Saving failed",
+                &format!("Failed to create {}: {}", parent.display(), e),
+                true,
+            );
+            return;
+        }
+        if let Err(e) = backups::write_atomic(&write.path, &write.updated) {
+            gui_ref.custom_error_popup(
+                "Saving failed",
+                &format!(
+                    "Failed to save the configuration to {}: {}",
+                    write.path.display(),
+                    e
+                ),
+                true,
+            );
+            return;
+        }
+    }
+
+    // Reload so widget state, row origins and pending changes match the files again
+    let path = gui_ref.config_path.clone();
+    if let Ok(config_str) = fs::read_to_string(&path) {
         let parsed_config = parse_config(&config_str);
+        gui_ref.load_config(&parsed_config);
+        gui_ref.watch_files(&path, &parsed_config);
+    }
 
-        for sourced_path in &parsed_config.sourced_paths {
-            let sourced_backup = Path::new(sourced_path).with_file_name(format!(
-                "{}{}",
-                Path::new(sourced_path)
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap(),
-                BACKUP_SUFFIX
-            ));
-            if sourced_backup.exists() {
-                if let Err(e) = fs::copy(&sourced_backup, sourced_path) {
-                    gui_ref.custom_error_popup(
-                        "Undo Failed",
-                        &format!("Failed to restore sourced file from backup: {}", e),
-                        true,
-                    );
-                    return;
-                }
-                if let Err(e) = fs::remove_file(&sourced_backup) {
-                    gui_ref.custom_error_popup(
-                        "Backup Deletion Failed",
-                        &format!("Failed to delete sourced backup file: {}", e),
-                        true,
-                    );
-                }
-            }
+    if gui_ref.settings.borrow().reload_after_save && gui_ref.client.is_some() {
+        drop(gui_ref);
+        glib::MainContext::default().spawn_local(reload_after_save(gui, close_after));
+        return;
+    }
+
+    if close_after {
+        gui_ref.get_changes().borrow_mut().clear();
+        let window = gui_ref.window.clone();
+        drop(gui_ref);
+        window.close();
+    }
+}
+
+/// Have Hyprland reload the saved files and highlight the rows it reports errors for.
+/// With `close_after`, the window only closes when there are none.
+async fn reload_after_save(gui: Rc<RefCell<gui::ConfigGUI>>, close_after: bool) {
+    let client = gui.borrow().client.clone();
+    let errors = match config_errors::reload(client.clone()).await {
+        Ok(()) => config_errors::query(client).await,
+        Err(e) => {
+            eprintln!("Failed to reload Hyprland: {}", e);
+            Vec::new()
+        }
+    };
+
+    let mut gui_ref = gui.borrow_mut();
+    if !errors.is_empty() {
+        let path = gui_ref.config_path.clone();
+        if let Ok(config_str) = fs::read_to_string(&path) {
+            gui_ref.flag_config_errors(&parse_config(&config_str), &errors);
+        }
+        let labels: Vec<String> = errors.iter().map(|e| e.label()).collect();
+        gui_ref.custom_error_popup(
+            "Config Errors",
+            &format!(
+                "Hyprland reported {} error(s) after reloading. Affected rows are highlighted.\n\n{}",
+                errors.len(),
+                labels.join("\n")
+            ),
+            false,
+        );
+        // Keep the window open so the errors can be fixed
+        return;
+    }
+
+    if close_after {
+        gui_ref.get_changes().borrow_mut().clear();
+        let window = gui_ref.window.clone();
+        drop(gui_ref);
+        window.close();
+    }
+}
+
+/// Restore the newest backup taken on save that contains the current config file and
+/// differs from the files on disk, so each Undo steps back one more save.
+fn undo_changes(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let path = gui.borrow().config_path.clone();
+    let path = fs::canonicalize(&path).unwrap_or(path);
+    let latest = backups::list().into_iter().find(|set| {
+        !set.before_restore
+            && set.files.iter().any(|file| file.original == path)
+            && !set.matches_disk()
+    });
+    match latest {
+        Some(set) => restore_backup(gui, &set),
+        None => gui.borrow_mut().custom_error_popup(
+            "Undo Failed",
+            "No earlier backup found. Save changes at least once to create a backup.",
+            true,
+        ),
+    }
+}
+
+fn restore_backup(gui: Rc<RefCell<gui::ConfigGUI>>, set: &backups::BackupSet) {
+    let mut gui_ref = gui.borrow_mut();
+
+    let contents = match set.contents() {
+        Ok(contents) => contents,
+        Err(e) => {
+            gui_ref.custom_error_popup(
+                "Restore Failed",
+                &format!("Failed to read the backup: {}", e),
+                true,
+            );
+            return;
         }
+    };
+
+    // Back up the current files first, so the restore itself can be undone, unless the
+    // newest backup already holds them
+    let files: Vec<PathBuf> = contents.iter().map(|(path, _)| path.clone()).collect();
+    let backed_up = backups::list().into_iter().next().is_some_and(|newest| {
+        files
+            .iter()
+            .all(|path| newest.files.iter().any(|file| file.original == *path))
+            && newest.matches_disk()
+    });
+    let backup_limit = gui_ref.settings.borrow().backup_limit;
+    if !backed_up && let Err(e) = backups::create(&files, backup_limit, true) {
+        gui_ref.custom_error_popup(
+            "Backup failed",
+            &format!("Failed to create backup: {}", e),
+            true,
+        );
+        return;
+    }
 
-        match fs::copy(&backup_path, &path) {
-            Ok(_) => {
-                if let Ok(config_str) = fs::read_to_string(&path) {
-                    let parsed_config = parse_config(&config_str);
-                    gui_ref.load_config(&parsed_config);
-                    gui_ref.get_changes().borrow_mut().clear();
+    for (path, content) in &contents {
+        if let Err(e) = backups::write_atomic(path, content) {
+            gui_ref.custom_error_popup(
+                "Restore Failed",
+                &format!("Failed to restore {}: {}", path.display(), e),
+                true,
+            );
+            return;
+        }
+    }
 
-                    if let Err(e) = fs::remove_file(&backup_path) {
-                        gui_ref.custom_error_popup(
-                            "Backup Deletion Failed",
-                            &format!("Failed to delete the backup file: {}", e),
-                            true,
-                        );
+    let path = gui_ref.config_path.clone();
+    match fs::read_to_string(&path) {
+        Ok(config_str) => {
+            let parsed_config = parse_config(&config_str);
+            gui_ref.load_config(&parsed_config);
+            gui_ref.watch_files(&path, &parsed_config);
+            gui_ref.get_changes().borrow_mut().clear();
+            gui_ref.custom_info_popup(
+                "Restore Successful",
+                &format!(
+                    "Configuration restored from the backup of {}.",
+                    set.created_label()
+                ),
+                true,
+            );
+        }
+        Err(_) => {
+            gui_ref.custom_error_popup(
+                "Reload Failed",
+                "Failed to reload the configuration after restoring.",
+                true,
+            );
+        }
+    }
+}
+
+fn show_backups_dialog(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    let dialog = gtk::Window::builder()
+        .title("Backups")
+        .modal(true)
+        .transient_for(&gui.borrow().window)
+        .default_width(480)
+        .default_height(360)
+        .build();
+
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 10);
+    vbox.set_margin_top(10);
+    vbox.set_margin_bottom(10);
+    vbox.set_margin_start(10);
+    vbox.set_margin_end(10);
+
+    let list = gtk::ListBox::new();
+    list.set_selection_mode(gtk::SelectionMode::Single);
+    let placeholder = gtk::Label::new(Some("No backups yet."));
+    list.set_placeholder(Some(&placeholder));
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_vexpand(true);
+    scrolled.set_child(Some(&list));
+    vbox.append(&scrolled);
+
+    let sets: Rc<RefCell<Vec<backups::BackupSet>>> = Rc::new(RefCell::new(Vec::new()));
+    let fill = {
+        let list = list.clone();
+        let sets = sets.clone();
+        move || {
+            while let Some(row) = list.first_child() {
+                list.remove(&row);
+            }
+            *sets.borrow_mut() = backups::list();
+            for set in sets.borrow().iter() {
+                let label = gtk::Label::new(Some(&format!(
+                    "{} — {} file(s){}",
+                    set.created_label(),
+                    set.files.len(),
+                    if set.before_restore {
+                        ", before a restore"
                     } else {
-                        gui_ref.custom_info_popup(
-                            "Undo Successful",
-                            "Configuration restored from backup and backup files deleted.",
-                            true,
-                        );
+                        ""
                     }
-                } else {
-                    gui_ref.custom_error_popup(
-                        "Reload Failed",
-                        "Failed to reload the configuration after undo.",
-                        true,
-                    );
-                }
-            }
-            Err(e) => {
-                gui_ref.custom_error_popup(
-                    "Undo Failed",
-                    &format!("Failed to restore from backup: {}", e),
-                    true,
-                );
+                )));
+                label.set_halign(gtk::Align::Start);
+                label.set_margin_top(5);
+                label.set_margin_bottom(5);
+                let files: Vec<String> = set
+                    .files
+                    .iter()
+                    .map(|f| f.original.display().to_string())
+                    .collect();
+                label.set_tooltip_text(Some(&files.join("\n")));
+                list.append(&label);
             }
         }
-    } else {
-        gui_ref.custom_error_popup(
-            "Undo Failed",
-            "No backup file found. Save changes at least once to create a backup.",
+    };
+    fill();
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    buttons.set_halign(gtk::Align::End);
+    let delete_button = Button::with_label("Delete");
+    let restore_button = Button::with_label("Restore");
+    restore_button.add_css_class("suggested-action");
+    let close_button = Button::with_label("Close");
+    buttons.append(&delete_button);
+    buttons.append(&close_button);
+    buttons.append(&restore_button);
+    vbox.append(&buttons);
+
+    let list_clone = list.clone();
+    let sets_clone = sets.clone();
+    let gui_clone = gui.clone();
+    delete_button.connect_clicked(move |_| {
+        if let Some(row) = list_clone.selected_row()
+            && let Some(set) = sets_clone.borrow().get(row.index() as usize)
+            && let Err(e) = set.delete()
+        {
+            gui_clone.borrow_mut().custom_error_popup(
+                "Deletion Failed",
+                &format!("Failed to delete the backup: {}", e),
+                true,
+            );
+        }
+        fill();
+    });
+
+    let dialog_clone = dialog.clone();
+    close_button.connect_clicked(move |_| dialog_clone.close());
+
+    let dialog_clone = dialog.clone();
+    restore_button.connect_clicked(move |_| {
+        let selected = list
+            .selected_row()
+            .and_then(|row| sets.borrow().get(row.index() as usize).cloned());
+        if let Some(set) = selected {
+            dialog_clone.close();
+            restore_backup(gui.clone(), &set);
+        }
+    });
+
+    dialog.set_child(Some(&vbox));
+    dialog.present();
+}
+
+fn choose_config_file(gui: Rc<RefCell<gui::ConfigGUI>>) {
+    if !gui.borrow().get_changes().borrow().is_empty() {
+        gui.borrow_mut().custom_error_popup(
+            "Unsaved Changes",
+            "Save or undo your pending changes before opening another file.",
             true,
         );
+        return;
     }
+
+    glib::MainContext::default().spawn_local(async move {
+        let file_chooser = gtk::FileChooserDialog::new(
+            Some("Open Hyprland Config"),
+            Some(&gui.borrow().window),
+            gtk::FileChooserAction::Open,
+            &[
+                ("Cancel", gtk::ResponseType::Cancel),
+                ("Open", gtk::ResponseType::Accept),
+            ],
+        );
+        if let Some(dir) = gui.borrow().config_path.parent() {
+            let _ = file_chooser.set_current_folder(Some(&gtk::gio::File::for_path(dir)));
+        }
+
+        if file_chooser.run_future().await == gtk::ResponseType::Accept
+            && let Some(file) = file_chooser.file()
+            && let Some(path) = file.path()
+        {
+            open_config_file(gui.clone(), path);
+        }
+        file_chooser.close();
+    });
 }
 
-fn get_config_path() -> PathBuf {
-    Path::new(&env::var("HOME").unwrap_or_else(|_| ".".to_string())).join(CONFIG_PATH)
+/// Switch to another config file; saving, backups and undo follow it from then on.
+fn open_config_file(gui: Rc<RefCell<gui::ConfigGUI>>, path: PathBuf) {
+    let mut gui_ref = gui.borrow_mut();
+    match fs::read_to_string(&path) {
+        Ok(config_str) => {
+            let parsed_config = parse_config(&config_str);
+            gui_ref.config_path = path.clone();
+            gui_ref.load_config(&parsed_config);
+            gui_ref.watch_files(&path, &parsed_config);
+        }
+        Err(e) => gui_ref.custom_error_popup(
+            "Reading failed",
+            &format!("Failed to read {}: {}", path.display(), e),
+            true,
+        ),
+    }
+}
+
+/// The config to edit: the `--config` argument, then the file the running Hyprland was
+/// started with, then `$XDG_CONFIG_HOME/hypr/hyprland.conf`.
+fn resolve_config_path(cli_path: Option<PathBuf>) -> PathBuf {
+    cli_path
+        .or_else(running_hyprland_config)
+        .unwrap_or_else(|| {
+            settings::xdg_dir("XDG_CONFIG_HOME", ".config")
+                .join("hypr")
+                .join("hyprland.conf")
+        })
+}
+
+/// The `--config`/`-c` argument of a Hyprland process owned by the current user.
+fn running_hyprland_config() -> Option<PathBuf> {
+    let uid = fs::metadata("/proc/self").ok()?.uid();
+    for entry in fs::read_dir("/proc").ok()?.flatten() {
+        let dir = entry.path();
+        let is_hyprland = fs::read_to_string(dir.join("comm"))
+            .is_ok_and(|comm| comm.trim() == "Hyprland")
+            && fs::metadata(&dir).is_ok_and(|m| m.uid() == uid);
+        if !is_hyprland {
+            continue;
+        }
+        let Ok(cmdline) = fs::read(dir.join("cmdline")) else {
+            continue;
+        };
+        let args: Vec<String> = cmdline
+            .split(|b| *b == 0)
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect();
+        for (idx, arg) in args.iter().enumerate() {
+            let value = match arg.as_str() {
+                "--config" | "-c" => args.get(idx + 1).cloned(),
+                _ => arg.strip_prefix("--config=").map(|v| v.to_string()),
+            };
+            if let Some(value) = value {
+                let path = match value.strip_prefix("~/") {
+                    Some(rest) => Path::new(&env::var("HOME").unwrap_or_default()).join(rest),
+                    None => PathBuf::from(value),
+                };
+                // Relative paths are relative to where Hyprland was started
+                return Some(match fs::read_link(dir.join("cwd")) {
+                    Ok(cwd) if path.is_relative() => cwd.join(path),
+                    _ => path,
+                });
+            }
+        }
+    }
+    None
 }
@@ -1,19 +1,38 @@
-use gtk::{Box, Orientation, ScrolledWindow, Widget, prelude::*};
+use gtk::{Box, Orientation, ScrolledWindow, Widget, glib, prelude::*};
 
 use hyprparser::HyprlandConfig;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::process::Command;
+use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
 
-use serde_json::Value;
-
+use crate::config_lines;
+use crate::defaults::Defaults;
 use crate::gui::add_dropdown_option;
+use crate::history::EditHistory;
+use crate::ipc::{self, SocketClient};
+use crate::keybinds::{self, BindEdits, BindGroup, BindTable};
+use crate::monitor_canvas;
+use crate::monitors::{self, MonitorData, RuleTables};
+use crate::profiles::{self, Profiles};
 use crate::widgets::WidgetBuilder;
 
 pub struct ConfigWidget {
     pub options: HashMap<String, Widget>,
     pub scrolled_window: ScrolledWindow,
+    pub rule_tables: RuleTables,
+    pub bind_table: BindTable,
+    /// Profile and layout controls of the Monitors page, which aren't config options.
+    monitor_controls: Option<MonitorControls>,
+}
+
+/// Widgets of the Monitors page that act on the whole layout.
+struct MonitorControls {
+    profiles: gtk::DropDown,
+    active_profile: gtk::Label,
+    profile_suggestion: Box,
+    apply: gtk::Button,
 }
 
 impl ConfigWidget {
@@ -31,6 +50,9 @@ impl ConfigWidget {
         scrolled_window.set_child(Some(&container));
 
         let mut options = HashMap::new();
+        let rule_tables = RuleTables::default();
+        let bind_table = BindTable::default();
+        let mut monitor_controls = None;
 
         let first_section = Rc::new(RefCell::new(true));
 
@@ -43,6 +65,63 @@ impl ConfigWidget {
                     first_section.clone(),
                 );
 
+                // Profiles
+                WidgetBuilder::add_section(
+                    &container,
+                    "Profiles",
+                    "Switch between saved monitor setups. The active profile is written to hyprgui-monitors.conf next to the config.",
+                    first_section.clone(),
+                );
+                let profile_controls = Box::new(Orientation::Horizontal, 8);
+                profile_controls.set_margin_top(8);
+                profile_controls.set_margin_start(10);
+                let profile_dd = gtk::DropDown::from_strings(&[]);
+                profile_dd.set_width_request(200);
+                profile_controls.append(&profile_dd);
+                let activate_btn = gtk::Button::with_label("Activate");
+                activate_btn.set_action_name(Some("win.activate-profile"));
+                activate_btn
+                    .set_tooltip_text(Some("Write the profile to its file and reload Hyprland"));
+                profile_controls.append(&activate_btn);
+                let save_profile_btn = gtk::Button::with_label("Save as Profile…");
+                save_profile_btn.set_action_name(Some("win.save-profile"));
+                save_profile_btn.set_tooltip_text(Some(
+                    "Store the monitor and workspace rows as a named profile",
+                ));
+                profile_controls.append(&save_profile_btn);
+                let delete_profile_btn = gtk::Button::with_label("Delete");
+                delete_profile_btn.set_action_name(Some("win.delete-profile"));
+                profile_controls.append(&delete_profile_btn);
+                let active_label = gtk::Label::new(None);
+                active_label.set_margin_start(8);
+                active_label.add_css_class("dim-label");
+                profile_controls.append(&active_label);
+                container.append(&profile_controls);
+
+                // Filled in once the connected monitors are known
+                let suggestion = Box::new(Orientation::Horizontal, 8);
+                suggestion.set_margin_top(8);
+                suggestion.set_margin_start(10);
+                suggestion.set_visible(false);
+                let suggestion_label = gtk::Label::new(None);
+                suggestion.append(&suggestion_label);
+                let suggestion_btn = gtk::Button::with_label("Activate");
+                suggestion_btn.set_action_name(Some("win.activate-profile"));
+                suggestion.append(&suggestion_btn);
+                container.append(&suggestion);
+
+                let buttons = [activate_btn.clone(), delete_profile_btn.clone()];
+                profile_dd.connect_selected_notify(move |dd| {
+                    let name = dd
+                        .selected_item()
+                        .and_downcast::<gtk::StringObject>()
+                        .map(|o| o.string().to_variant());
+                    for button in &buttons {
+                        button.set_action_target_value(name.as_ref());
+                        button.set_sensitive(name.is_some());
+                    }
+                });
+
                 // Monitors table
                 WidgetBuilder::add_section(
                     &container,
@@ -50,6 +129,8 @@ impl ConfigWidget {
                     "Configure monitors",
                     first_section.clone(),
                 );
+                container.append(&monitor_canvas::new(&rule_tables));
+
                 // Monitors header row
                 let mon_header = Box::new(Orientation::Horizontal, 6);
                 let l_name = gtk::Label::new(Some("Name"));
@@ -81,6 +162,11 @@ impl ConfigWidget {
                 mon_controls.set_margin_top(8);
                 let add_mon_btn = gtk::Button::with_label("Add monitor");
                 mon_controls.append(&add_mon_btn);
+                let apply_mon_btn = gtk::Button::with_label("Apply now");
+                apply_mon_btn.set_tooltip_text(Some(
+                    "Try the monitor layout on the running Hyprland, reverting it unless confirmed",
+                ));
+                mon_controls.append(&apply_mon_btn);
                 container.append(&mon_controls);
 
                 // Workspaces table
@@ -92,8 +178,8 @@ impl ConfigWidget {
                 );
                 // Workspaces header row
                 let ws_header = Box::new(Orientation::Horizontal, 6);
-                let l_num = gtk::Label::new(Some("Number"));
-                l_num.set_width_request(80);
+                let l_num = gtk::Label::new(Some("Workspace"));
+                l_num.set_width_request(140);
                 l_num.set_xalign(0.5);
                 let l_mon = gtk::Label::new(Some("Monitor Name"));
                 l_mon.set_width_request(140);
@@ -122,9 +208,57 @@ impl ConfigWidget {
                 // Store for later access in load_config
                 options.insert("monitors:list_monitors".to_string(), list_mon.upcast());
                 options.insert("monitors:add_monitor".to_string(), add_mon_btn.upcast());
-
                 options.insert("monitors:list_workspaces".to_string(), list_ws.upcast());
                 options.insert("monitors:add_workspace".to_string(), add_ws_btn.upcast());
+                monitor_controls = Some(MonitorControls {
+                    profiles: profile_dd,
+                    active_profile: active_label,
+                    profile_suggestion: suggestion,
+                    apply: apply_mon_btn,
+                });
+            }
+            "keybinds" => {
+                WidgetBuilder::add_section(
+                    &container,
+                    "Keybinds",
+                    "Every bind line from the main and sourced files, in the order Hyprland reads them. Binds move within their file and submap.",
+                    first_section.clone(),
+                );
+
+                let header = Box::new(Orientation::Horizontal, 6);
+                for (title, width) in [
+                    ("Flags", 70),
+                    ("Modifiers", 140),
+                    ("Key", 120),
+                    ("Description", 160),
+                    ("Dispatcher", 180),
+                    ("Argument", 140),
+                ] {
+                    let label = gtk::Label::new(Some(title));
+                    label.set_width_request(width);
+                    label.set_xalign(0.5);
+                    header.append(&label);
+                }
+                container.append(&header);
+                let list = gtk::ListBox::new();
+                list.set_selection_mode(gtk::SelectionMode::Single);
+                list.set_widget_name("keybinds_list");
+                list.set_margin_top(10);
+                list.set_margin_start(10);
+                list.set_margin_end(10);
+                container.append(&list);
+
+                let controls = Box::new(Orientation::Horizontal, 8);
+                controls.set_margin_top(8);
+                let add_btn = gtk::Button::with_label("Add keybind");
+                add_btn.set_tooltip_text(Some(
+                    "Add a bind below the selected one, in the same file and submap",
+                ));
+                controls.append(&add_btn);
+                container.append(&controls);
+
+                options.insert("keybinds:list".to_string(), list.upcast());
+                options.insert("keybinds:add".to_string(), add_btn.upcast());
             }
             "general" => {
                 WidgetBuilder::add_section(
@@ -1982,16 +2116,26 @@ impl ConfigWidget {
         ConfigWidget {
             options,
             scrolled_window,
+            rule_tables,
+            bind_table,
+            monitor_controls,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn load_config(
         &self,
         config: &HyprlandConfig,
         category: &str,
         changed_options: Rc<RefCell<HashMap<(String, String), String>>>,
+        history: Rc<RefCell<EditHistory>>,
+        defaults: &Defaults,
+        config_path: &Path,
+        drm_root: &Path,
+        client: Option<&Arc<SocketClient>>,
     ) {
         if category == "monitors" {
+            self.load_profiles(config_path);
             // Populate the monitors and workspaces ListBoxes from config
             if let (Some(list_mon_w), Some(list_ws_w)) = (
                 self.options.get("monitors:list_monitors"),
@@ -2007,407 +2151,235 @@ impl ConfigWidget {
                 while let Some(row) = list_ws.first_child() {
                     list_ws.remove(&row);
                 }
+                self.rule_tables.monitors.borrow_mut().clear();
+                self.rule_tables.workspaces.borrow_mut().clear();
+                self.rule_tables.removed.borrow_mut().clear();
 
-                // Gather lines from aggregated content and sourced files
-                let mut mon_lines: Vec<String> = Vec::new();
-                let mut ws_lines: Vec<String> = Vec::new();
-                for ln in &config.content {
-                    let t = ln.trim();
-                    if t.starts_with("monitor=") {
-                        mon_lines.push(t.to_string());
-                    } else if t.starts_with("workspace=") {
-                        ws_lines.push(t.to_string());
-                    }
-                }
-                for sourced in &config.sourced_content {
-                    for ln in sourced {
-                        let t = ln.trim();
-                        if t.starts_with("monitor=") {
-                            mon_lines.push(t.to_string());
-                        } else if t.starts_with("workspace=") {
-                            ws_lines.push(t.to_string());
-                        }
-                    }
-                }
+                // Gather lines from the main config and sourced files
+                let mon_rules = monitors::collect_rules(config, monitors::MONITOR_KEYWORD);
+                let ws_rules = monitors::collect_rules(config, monitors::WORKSPACE_KEYWORD);
 
-                // Determine available monitor names and modes via hyprctl -j monitors
-                let mut monitor_names: Vec<String> = Vec::new();
-                let mut name_to_modes: HashMap<String, Vec<String>> = HashMap::new();
-                let mut all_modes: Vec<String> = vec!["preferred".to_string()];
-                if let Ok(out) = Command::new("hyprctl").args(["-j", "monitors"]).output()
-                    && out.status.success()
-                    && let Ok(json) = serde_json::from_slice::<Value>(&out.stdout)
-                    && let Some(arr) = json.as_array()
-                {
-                    for m in arr {
-                        if let Some(name) = m.get("name").and_then(|v| v.as_str()) {
-                            let name_str = name.to_string();
-                            if !monitor_names.contains(&name_str) {
-                                monitor_names.push(name_str.clone());
-                            }
-                            // collect availableModes if provided
-                            let mut modes_for_this: Vec<String> = Vec::new();
-                            if let Some(val) = m.get("availableModes").or_else(|| m.get("modes")) {
-                                if let Some(s) = val.as_str() {
-                                    for tok in s.split_whitespace() {
-                                        let t = tok.trim().to_string();
-                                        if !t.is_empty() && !modes_for_this.contains(&t) {
-                                            modes_for_this.push(t.clone());
-                                        }
-                                    }
-                                } else if let Some(arrm) = val.as_array() {
-                                    for item in arrm {
-                                        if let Some(s) = item.as_str() {
-                                            let t = s.trim().to_string();
-                                            if !t.is_empty() && !modes_for_this.contains(&t) {
-                                                modes_for_this.push(t.clone());
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            if !modes_for_this.is_empty() {
-                                for mm in &modes_for_this {
-                                    if !all_modes.contains(mm) {
-                                        all_modes.push(mm.clone());
-                                    }
-                                }
-                                name_to_modes.insert(name_str, modes_for_this);
-                            }
-                        }
-                    }
-                }
-                if monitor_names.is_empty() {
-                    // Fallback: infer names from existing lines
-                    for l in &mon_lines {
-                        if let Some(rest) = l.split_once('=').map(|x| x.1)
-                            && let Some(name) = rest.split(',').next()
-                        {
-                            let name = name.trim();
-                            if !name.is_empty() && !monitor_names.contains(&name.to_string()) {
-                                monitor_names.push(name.to_string());
-                            }
-                        }
+                let values: Vec<String> = mon_rules.iter().map(|(_, v)| v.clone()).collect();
+
+                let loading = gtk::Label::new(Some("Querying monitors…"));
+                loading.set_halign(gtk::Align::Start);
+                list_mon.set_placeholder(Some(&loading));
+
+                // Rows are filled in once the compositor answered, without blocking the UI
+                let list_mon = list_mon.clone();
+                let list_ws = list_ws.clone();
+                let add_buttons = (
+                    self.options
+                        .get("monitors:add_monitor")
+                        .and_then(|w| w.downcast_ref::<gtk::Button>().cloned()),
+                    self.options
+                        .get("monitors:add_workspace")
+                        .and_then(|w| w.downcast_ref::<gtk::Button>().cloned()),
+                );
+                let apply_button = self.monitor_controls.as_ref().map(|c| c.apply.clone());
+                let suggestion = self
+                    .monitor_controls
+                    .as_ref()
+                    .map(|c| c.profile_suggestion.clone());
+                let active = profiles::active_profile(&profiles::profile_path(config_path));
+                let tables = self.rule_tables.clone();
+                let drm_root = drm_root.to_path_buf();
+                let client = client.cloned();
+                glib::MainContext::default().spawn_local(async move {
+                    let monitors = monitors::connected_monitors(client.clone(), &drm_root).await;
+                    *tables.monitor_data.borrow_mut() =
+                        MonitorData::from_monitors(&monitors, &values);
+                    if let Some(suggestion) = suggestion {
+                        show_profile_suggestion(&suggestion, &monitors, active.as_deref());
                     }
-                }
 
-                if mon_lines.is_empty() {
-                    let row = gtk::Label::new(Some("No monitors defined."));
-                    row.set_halign(gtk::Align::Start);
-                    list_mon.append(&row);
-                } else {
-                    for l in mon_lines {
-                        // monitor=NAME,MODE,POS,SCALE
-                        let mut name_opt: Option<String> = None;
-                        let mut mode = "".to_string();
-                        let mut pos = "".to_string();
-                        let mut scale = "".to_string();
-                        if let Some(rest) = l.split_once('=').map(|x| x.1) {
-                            let parts: Vec<&str> = rest.split(',').collect();
-                            if !parts.is_empty() {
-                                name_opt = Some(parts[0].trim().to_string());
-                            }
-                            if parts.len() >= 2 {
-                                mode = parts[1].trim().to_string();
-                            }
-                            if parts.len() >= 3 {
-                                pos = parts[2].trim().to_string();
-                            }
-                            if parts.len() >= 4 {
-                                scale = parts[3].trim().to_string();
-                            }
-                        }
-                        let row = Box::new(Orientation::Horizontal, 6);
-                        // Monitor dropdown
-                        let items = monitor_names.clone();
-                        let items_refs: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
-                        let string_list = gtk::StringList::new(&items_refs);
-                        let dd = gtk::DropDown::new(Some(string_list), None::<gtk::Expression>);
-                        dd.set_width_request(140);
-                        dd.set_halign(gtk::Align::Start);
-                        if let Some(sel) = name_opt
-                            && let Some(model) = dd.model()
-                        {
-                            for i in 0..model.n_items() {
-                                if let Some(item) = model.item(i)
-                                    && let Some(obj) = item.downcast_ref::<gtk::StringObject>()
-                                    && obj.string() == sel
-                                {
-                                    dd.set_selected(i);
-                                    break;
-                                }
-                            }
-                        }
-                        row.append(&dd);
-                        // mode dropdown
-                        let mut modes_list: Vec<String> = vec!["preferred".to_string()];
-                        if let Some(model) = dd.model() {
-                            if let Some(item) = model.item(dd.selected())
-                                && let Some(obj) = item.downcast_ref::<gtk::StringObject>()
-                            {
-                                let sel = obj.string().to_string();
-                                if let Some(v) = name_to_modes.get(&sel) {
-                                    modes_list.extend(v.clone());
-                                } else {
-                                    modes_list.extend(all_modes.clone());
-                                }
-                            }
-                        } else {
-                            modes_list.extend(all_modes.clone());
-                        }
-                        let mut seen = std::collections::HashSet::new();
-                        modes_list.retain(|s| seen.insert(s.clone()));
-                        let modes_refs: Vec<&str> = modes_list.iter().map(|s| s.as_str()).collect();
-                        let modes_model = gtk::StringList::new(&modes_refs);
-                        let dd_mode =
-                            gtk::DropDown::new(Some(modes_model), None::<gtk::Expression>);
-                        dd_mode.set_width_request(220);
-                        if let Some(modelm) = dd_mode.model() {
-                            for i in 0..modelm.n_items() {
-                                if let Some(it) = modelm.item(i)
-                                    && let Some(obj) = it.downcast_ref::<gtk::StringObject>()
-                                    && obj.string() == mode
-                                {
-                                    dd_mode.set_selected(i);
-                                    break;
-                                }
-                            }
-                        }
-                        let e_pos = gtk::Entry::new();
-                        e_pos.set_placeholder_text(Some("pos e.g. 0x0 or auto"));
-                        e_pos.set_text(&pos);
-                        let e_scale = gtk::Entry::new();
-                        e_scale.set_placeholder_text(Some("scale e.g. 1"));
-                        e_scale.set_text(&scale);
-                        row.append(&dd_mode);
-                        row.append(&e_pos);
-                        row.append(&e_scale);
-                        // delete button
-                        let del_btn = gtk::Button::from_icon_name("window-close-symbolic");
-                        del_btn.set_has_frame(false);
-                        del_btn.add_css_class("flat");
-                        let list_ref = list_mon.clone();
-                        del_btn.connect_clicked(move |button| {
-                            if let Some(ancestor) = button.ancestor(gtk::ListBoxRow::static_type())
-                                && let Some(lb_row) = ancestor.downcast_ref::<gtk::ListBoxRow>()
-                            {
-                                list_ref.remove(lb_row);
-                            }
-                        });
-                        row.append(&del_btn);
-                        list_mon.append(&row);
+                    let placeholder = gtk::Label::new(Some("No monitors defined."));
+                    placeholder.set_halign(gtk::Align::Start);
+                    list_mon.set_placeholder(Some(&placeholder));
+                    for rule in mon_rules {
+                        monitors::add_monitor_row(&list_mon, Some(rule), &tables, &changed_options);
                     }
-                }
+                    tables.mark_confirmed();
+                    tables.layout_changed();
 
-                // Workspaces: workspace number dropdown, monitor dropdown, default switch
-                if ws_lines.is_empty() {
-                    let row = gtk::Label::new(Some("No workspaces mapped to monitors."));
-                    row.set_halign(gtk::Align::Start);
-                    list_ws.append(&row);
-                } else {
-                    for l in ws_lines {
-                        // workspace=NUM,monitor:NAME,default:true
-                        let mut ws_num = "1".to_string();
-                        let mut mon_name: Option<String> = None;
-                        let mut is_default = false;
-                        if let Some(rest) = l.split_once('=').map(|x| x.1) {
-                            for part in rest.split(',') {
-                                let p = part.trim();
-                                if let Some(num) = p.strip_prefix("monitor:") {
-                                    mon_name = Some(num.to_string());
-                                } else if let Some(def) = p.strip_prefix("default:") {
-                                    is_default = def == "true";
-                                } else if p.chars().all(|c| c.is_ascii_digit()) {
-                                    ws_num = p.to_string();
-                                }
-                            }
-                        }
-                        let row = Box::new(Orientation::Horizontal, 6);
-                        // workspace number dropdown
-                        let ws_numbers: Vec<String> = (1..=20).map(|n| n.to_string()).collect();
-                        let ws_num_refs: Vec<&str> =
-                            ws_numbers.iter().map(|s| s.as_str()).collect();
-                        let ws_list = gtk::StringList::new(&ws_num_refs);
-                        let ws_dd = gtk::DropDown::new(Some(ws_list), None::<gtk::Expression>);
-                        ws_dd.set_width_request(80);
-                        if let Some(model) = ws_dd.model() {
-                            for i in 0..model.n_items() {
-                                if let Some(item) = model.item(i)
-                                    && let Some(obj) = item.downcast_ref::<gtk::StringObject>()
-                                    && obj.string() == ws_num
-                                {
-                                    ws_dd.set_selected(i);
-                                    break;
-                                }
-                            }
-                        }
-                        row.append(&ws_dd);
-                        // monitor dropdown
-                        let items = monitor_names.clone();
-                        let items_refs: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
-                        let mon_list = gtk::StringList::new(&items_refs);
-                        let mon_dd = gtk::DropDown::new(Some(mon_list), None::<gtk::Expression>);
-                        mon_dd.set_width_request(140);
-                        if let Some(sel) = mon_name
-                            && let Some(model) = mon_dd.model()
-                        {
-                            for i in 0..model.n_items() {
-                                if let Some(item) = model.item(i)
-                                    && let Some(obj) = item.downcast_ref::<gtk::StringObject>()
-                                    && obj.string() == sel
-                                {
-                                    mon_dd.set_selected(i);
-                                    break;
-                                }
-                            }
-                        }
-                        row.append(&mon_dd);
-                        // default switch
-                        let sw = gtk::Switch::new();
-                        sw.set_halign(gtk::Align::Start);
-                        sw.set_active(is_default);
-                        row.append(&sw);
-                        // delete button
-                        let del_btn = gtk::Button::from_icon_name("window-close-symbolic");
-                        del_btn.set_has_frame(false);
-                        del_btn.add_css_class("flat");
-                        let list_ref = list_ws.clone();
-                        del_btn.connect_clicked(move |button| {
-                            if let Some(ancestor) = button.ancestor(gtk::ListBoxRow::static_type())
-                                && let Some(lb_row) = ancestor.downcast_ref::<gtk::ListBoxRow>()
-                            {
-                                list_ref.remove(lb_row);
-                            }
-                        });
-                        row.append(&del_btn);
-                        list_ws.append(&row);
+                    // Workspaces: workspace number dropdown, monitor dropdown, default switch
+                    let placeholder = gtk::Label::new(Some("No workspaces mapped to monitors."));
+                    placeholder.set_halign(gtk::Align::Start);
+                    list_ws.set_placeholder(Some(&placeholder));
+                    for rule in ws_rules {
+                        monitors::add_workspace_row(
+                            &list_ws,
+                            Some(rule),
+                            &tables,
+                            &changed_options,
+                        );
                     }
-                }
+                    tables.replay_edits(&list_mon, &list_ws, &changed_options);
 
-                // Wire add buttons
-                if let (Some(add_mon_w), Some(add_ws_w)) = (
-                    self.options.get("monitors:add_monitor"),
-                    self.options.get("monitors:add_workspace"),
-                ) && let (Some(add_mon), Some(add_ws)) = (
-                    add_mon_w.downcast_ref::<gtk::Button>(),
-                    add_ws_w.downcast_ref::<gtk::Button>(),
-                ) {
-                    let list_clone = list_mon.clone();
-                    let monitor_names_clone = monitor_names.clone();
-                    let name_to_modes_clone = name_to_modes.clone();
-                    let all_modes_clone = all_modes.clone();
-                    add_mon.connect_clicked(move |_| {
-                        let row = Box::new(Orientation::Horizontal, 6);
-                        let items = monitor_names_clone.clone();
-                        let items_refs: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
-                        let string_list = gtk::StringList::new(&items_refs);
-                        let dd = gtk::DropDown::new(Some(string_list), None::<gtk::Expression>);
-                        dd.set_width_request(140);
-                        row.append(&dd);
-                        // initial modes
-                        let mut modes_list: Vec<String> = vec!["preferred".to_string()];
-                        modes_list.extend(all_modes_clone.clone());
-                        let mut seen = std::collections::HashSet::new();
-                        modes_list.retain(|s| seen.insert(s.clone()));
-                        let modes_refs: Vec<&str> = modes_list.iter().map(|s| s.as_str()).collect();
-                        let modes_model = gtk::StringList::new(&modes_refs);
-                        let dd_mode =
-                            gtk::DropDown::new(Some(modes_model), None::<gtk::Expression>);
-                        dd_mode.set_width_request(220);
-                        // update on monitor change
-                        let dd_mode_clone = dd_mode.clone();
-                        let name_to_modes_local = name_to_modes_clone.clone();
-                        let all_modes_local = all_modes_clone.clone();
-                        dd.connect_selected_notify(move |sel_dd| {
-                            let mut modes_list: Vec<String> = vec!["preferred".to_string()];
-                            if let Some(item) = sel_dd.selected_item() {
-                                if let Some(obj) = item.downcast_ref::<gtk::StringObject>() {
-                                    let sel = obj.string().to_string();
-                                    if let Some(v) = name_to_modes_local.get(&sel) {
-                                        modes_list.extend(v.clone());
-                                    } else {
-                                        modes_list.extend(all_modes_local.clone());
-                                    }
-                                }
-                            } else {
-                                modes_list.extend(all_modes_local.clone());
-                            }
-                            let mut seen = std::collections::HashSet::new();
-                            modes_list.retain(|s| seen.insert(s.clone()));
-                            let modes_refs: Vec<&str> =
-                                modes_list.iter().map(|s| s.as_str()).collect();
-                            let new_model = gtk::StringList::new(&modes_refs);
-                            dd_mode_clone.set_model(Some(&new_model));
-                            dd_mode_clone.set_selected(0);
-                        });
-                        let e_pos = gtk::Entry::new();
-                        e_pos.set_placeholder_text(Some("pos e.g. 0x0 or auto"));
-                        e_pos.set_text("auto");
-                        let e_scale = gtk::Entry::new();
-                        e_scale.set_placeholder_text(Some("scale e.g. 1"));
-                        e_scale.set_text("1");
-                        row.append(&dd_mode);
-                        row.append(&e_pos);
-                        row.append(&e_scale);
-                        // X button
-                        let del_btn = gtk::Button::from_icon_name("window-close-symbolic");
-                        del_btn.set_has_frame(false);
-                        del_btn.add_css_class("flat");
-                        let list_ref = list_clone.clone();
-                        del_btn.connect_clicked(move |button| {
-                            if let Some(ancestor) = button.ancestor(gtk::ListBoxRow::static_type())
-                                && let Some(lb_row) = ancestor.downcast_ref::<gtk::ListBoxRow>()
-                            {
-                                list_ref.remove(lb_row);
-                            }
+                    // Wire add buttons
+                    if let (Some(add_mon), Some(add_ws)) = add_buttons {
+                        let list_clone = list_mon.clone();
+                        let tables_clone = tables.clone();
+                        let changed = changed_options.clone();
+                        add_mon.connect_clicked(move |_| {
+                            monitors::add_monitor_row(&list_clone, None, &tables_clone, &changed);
                         });
-                        row.append(&del_btn);
-                        list_clone.append(&row);
-                    });
 
-                    let list_clone = list_ws.clone();
-                    let monitor_names_clone = monitor_names.clone();
-                    add_ws.connect_clicked(move |_| {
-                        let row = Box::new(Orientation::Horizontal, 6);
-                        let ws_numbers: Vec<String> = (1..=20).map(|n| n.to_string()).collect();
-                        let ws_num_refs: Vec<&str> =
-                            ws_numbers.iter().map(|s| s.as_str()).collect();
-                        let ws_list = gtk::StringList::new(&ws_num_refs);
-                        let ws_dd = gtk::DropDown::new(Some(ws_list), None::<gtk::Expression>);
-                        ws_dd.set_width_request(80);
-                        row.append(&ws_dd);
-                        let items = monitor_names_clone.clone();
-                        let items_refs: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
-                        let mon_list = gtk::StringList::new(&items_refs);
-                        let mon_dd = gtk::DropDown::new(Some(mon_list), None::<gtk::Expression>);
-                        mon_dd.set_width_request(140);
-                        row.append(&mon_dd);
-                        let sw = gtk::Switch::new();
-                        sw.set_active(true);
-                        row.append(&sw);
-                        // X button
-                        let del_btn = gtk::Button::from_icon_name("window-close-symbolic");
-                        del_btn.set_has_frame(false);
-                        del_btn.add_css_class("flat");
-                        let list_ref = list_clone.clone();
-                        del_btn.connect_clicked(move |button| {
-                            if let Some(ancestor) = button.ancestor(gtk::ListBoxRow::static_type())
-                                && let Some(lb_row) = ancestor.downcast_ref::<gtk::ListBoxRow>()
-                            {
-                                list_ref.remove(lb_row);
-                            }
+                        let list_clone = list_ws.clone();
+                        let tables_clone = tables.clone();
+                        let changed = changed_options.clone();
+                        add_ws.connect_clicked(move |_| {
+                            monitors::add_workspace_row(&list_clone, None, &tables_clone, &changed);
                         });
-                        row.append(&del_btn);
-                        list_clone.append(&row);
-                    });
 
-                    // per-row X buttons handle deletion; no section-level delete
-                }
+                        // per-row X buttons handle deletion; no section-level delete
+                    }
+
+                    if let Some(apply) = apply_button {
+                        let list_clone = list_mon.clone();
+                        let changed = changed_options.clone();
+                        apply.connect_clicked(move |_| {
+                            monitors::apply_layout(&list_clone, &tables, &changed, client.clone());
+                        });
+                    }
+                });
             }
             return;
         }
+        if category == "keybinds" {
+            self.load_binds(config, changed_options);
+            return;
+        }
         let mut builder = WidgetBuilder::new();
         builder.options = self.options.clone();
-        builder.load_config(config, category, changed_options);
+        builder.load_config(config, category, changed_options, history, defaults);
+    }
+
+    /// Fill the Keybinds list from the bind lines of every file.
+    fn load_binds(
+        &self,
+        config: &HyprlandConfig,
+        changed_options: Rc<RefCell<HashMap<(String, String), String>>>,
+    ) {
+        let (Some(list), Some(add_btn)) = (
+            self.options
+                .get("keybinds:list")
+                .and_then(|w| w.downcast_ref::<gtk::ListBox>()),
+            self.options
+                .get("keybinds:add")
+                .and_then(|w| w.downcast_ref::<gtk::Button>()),
+        ) else {
+            return;
+        };
+        while let Some(row) = list.first_child() {
+            list.remove(&row);
+        }
+        self.bind_table.rows.borrow_mut().clear();
+        self.bind_table.removed.borrow_mut().clear();
+
+        let placeholder = gtk::Label::new(Some("No keybinds defined."));
+        placeholder.set_halign(gtk::Align::Start);
+        list.set_placeholder(Some(&placeholder));
+        for (idx, bind) in keybinds::collect_binds(config).into_iter().enumerate() {
+            let group = bind.group.clone();
+            keybinds::add_bind_row(
+                list,
+                idx,
+                Some(bind),
+                group,
+                &self.bind_table,
+                &changed_options,
+            );
+        }
+        self.bind_table.record_changes(&changed_options, list);
+
+        // New binds join the file and submap of the bind above them
+        let main_group = BindGroup {
+            source: None,
+            submap: String::new(),
+            file: config_lines::source_label(config, None),
+        };
+        let list_clone = list.clone();
+        let table = self.bind_table.clone();
+        add_btn.connect_clicked(move |_| {
+            let rows = table.rows.borrow().len();
+            let position = list_clone
+                .selected_row()
+                .map(|row| row.index() as usize + 1)
+                .unwrap_or(rows);
+            let group = position
+                .checked_sub(1)
+                .and_then(|idx| table.group_at(idx))
+                .unwrap_or_else(|| main_group.clone());
+            keybinds::add_bind_row(&list_clone, position, None, group, &table, &changed_options);
+        });
+    }
+
+    /// Apply Keybinds edits made before the files were reloaded to the rows read from
+    /// them. Returns the number of edits flagged as changed on disk too.
+    pub fn replay_binds(
+        &self,
+        edits: BindEdits,
+        changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+    ) -> usize {
+        match self
+            .options
+            .get("keybinds:list")
+            .and_then(|w| w.downcast_ref::<gtk::ListBox>())
+        {
+            Some(list) => self.bind_table.replay_edits(list, edits, changed_options),
+            None => 0,
+        }
+    }
+
+    /// List the stored monitor profiles and name the one written for `config_path`.
+    pub fn load_profiles(&self, config_path: &Path) {
+        let Some(MonitorControls {
+            profiles: dropdown,
+            active_profile: label,
+            ..
+        }) = &self.monitor_controls
+        else {
+            return;
+        };
+        let names = Profiles::load().names();
+        let active = profiles::active_profile(&profiles::profile_path(config_path));
+        let refs: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
+        dropdown.set_model(Some(&gtk::StringList::new(&refs)));
+        if let Some(idx) = active
+            .as_ref()
+            .and_then(|a| names.iter().position(|n| n == a))
+        {
+            dropdown.set_selected(idx as u32);
+        }
+        // Point the buttons at the selection even if it didn't change
+        dropdown.notify("selected");
+        label.set_text(&match active {
+            Some(name) => format!("Active: {}", name),
+            None if names.is_empty() => "No profiles saved yet".to_string(),
+            None => "No profile active".to_string(),
+        });
+    }
+}
+
+/// Offer the profile made for the connected monitors, unless it is already active.
+fn show_profile_suggestion(suggestion: &Box, connected: &[ipc::Monitor], active: Option<&str>) {
+    let profiles = Profiles::load();
+    let Some(profile) = profiles
+        .suggest(connected)
+        .filter(|p| Some(p.name.as_str()) != active)
+    else {
+        suggestion.set_visible(false);
+        return;
+    };
+    if let Some(label) = suggestion.first_child().and_downcast::<gtk::Label>() {
+        label.set_text(&format!(
+            "The connected monitors match the “{}” profile.",
+            profile.name
+        ));
+    }
+    if let Some(button) = suggestion.last_child().and_downcast::<gtk::Button>() {
+        button.set_action_target_value(Some(&profile.name.to_variant()));
     }
+    suggestion.set_visible(true);
 }
@@ -0,0 +1,344 @@
+use gtk::{Box, DrawingArea, Label, Orientation, cairo, prelude::*};
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::monitors::{OutputRect, RuleTables};
+
+/// Screen distance within which a dragged output snaps to a neighbour's edge.
+const SNAP_DISTANCE: f64 = 12.0;
+const PADDING: f64 = 16.0;
+
+/// Maps layout coordinates to canvas pixels.
+#[derive(Clone, Copy)]
+struct View {
+    zoom: f64,
+    offset_x: f64,
+    offset_y: f64,
+    origin_x: f64,
+    origin_y: f64,
+}
+
+impl View {
+    fn fit(rects: &[OutputRect], width: f64, height: f64) -> Self {
+        let min_x = rects.iter().map(|r| r.x).fold(f64::INFINITY, f64::min);
+        let min_y = rects.iter().map(|r| r.y).fold(f64::INFINITY, f64::min);
+        let max_x = rects
+            .iter()
+            .map(|r| r.right())
+            .fold(f64::NEG_INFINITY, f64::max);
+        let max_y = rects
+            .iter()
+            .map(|r| r.bottom())
+            .fold(f64::NEG_INFINITY, f64::max);
+        if rects.is_empty() {
+            return View {
+                zoom: 1.0,
+                offset_x: 0.0,
+                offset_y: 0.0,
+                origin_x: 0.0,
+                origin_y: 0.0,
+            };
+        }
+        let (layout_w, layout_h) = ((max_x - min_x).max(1.0), (max_y - min_y).max(1.0));
+        let zoom = ((width - 2.0 * PADDING) / layout_w)
+            .min((height - 2.0 * PADDING) / layout_h)
+            .max(0.01);
+        View {
+            zoom,
+            offset_x: (width - layout_w * zoom) / 2.0,
+            offset_y: (height - layout_h * zoom) / 2.0,
+            origin_x: min_x,
+            origin_y: min_y,
+        }
+    }
+
+    fn to_screen(self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.offset_x + (x - self.origin_x) * self.zoom,
+            self.offset_y + (y - self.origin_y) * self.zoom,
+        )
+    }
+
+    fn to_layout(self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.origin_x + (x - self.offset_x) / self.zoom,
+            self.origin_y + (y - self.offset_y) / self.zoom,
+        )
+    }
+}
+
+/// The output being dragged and where it started.
+#[derive(Clone, Copy)]
+struct Drag {
+    row: usize,
+    start_x: f64,
+    start_y: f64,
+}
+
+/// Outputs that overlap another, and outputs outside the largest group of outputs
+/// connected by touching edges.
+fn find_problems(rects: &[OutputRect]) -> (Vec<(usize, usize)>, Vec<usize>) {
+    let mut overlaps = Vec::new();
+    // Label each output with its group, merging groups whenever two outputs touch
+    let mut group: Vec<usize> = (0..rects.len()).collect();
+    for (i, a) in rects.iter().enumerate() {
+        for (j, b) in rects.iter().enumerate().skip(i + 1) {
+            let overlap_w = a.right().min(b.right()) - a.x.max(b.x);
+            let overlap_h = a.bottom().min(b.bottom()) - a.y.max(b.y);
+            let overlapping = overlap_w > 0.0 && overlap_h > 0.0;
+            if overlapping {
+                overlaps.push((i, j));
+            }
+            let touching =
+                (overlap_w == 0.0 && overlap_h > 0.0) || (overlap_h == 0.0 && overlap_w > 0.0);
+            if (overlapping || touching) && group[i] != group[j] {
+                let (from, to) = (group[j], group[i]);
+                for g in group.iter_mut().filter(|g| **g == from) {
+                    *g = to;
+                }
+            }
+        }
+    }
+    let size = |label: usize| group.iter().filter(|g| **g == label).count();
+    // On a tie, keep the group of the first output
+    let largest = (0..rects.len()).rev().max_by_key(|label| size(*label));
+    let detached = (0..rects.len())
+        .filter(|i| Some(group[*i]) != largest)
+        .collect();
+    (overlaps, detached)
+}
+
+/// Move `moving` to the closest neighbour edge within `distance` on each axis.
+fn snap(rects: &[OutputRect], moving: &OutputRect, distance: f64) -> (f64, f64) {
+    let mut best_x = (distance, moving.x);
+    let mut best_y = (distance, moving.y);
+    for other in rects.iter().filter(|r| r.row != moving.row) {
+        for x in [
+            other.right(),
+            other.x - moving.width,
+            other.x,
+            other.right() - moving.width,
+        ] {
+            if (x - moving.x).abs() < best_x.0 {
+                best_x = ((x - moving.x).abs(), x);
+            }
+        }
+        for y in [
+            other.bottom(),
+            other.y - moving.height,
+            other.y,
+            other.bottom() - moving.height,
+        ] {
+            if (y - moving.y).abs() < best_y.0 {
+                best_y = ((y - moving.y).abs(), y);
+            }
+        }
+    }
+    (best_x.1, best_y.1)
+}
+
+fn draw(cr: &cairo::Context, rects: &[OutputRect], view: View, dragging: Option<usize>) {
+    let (overlaps, detached) = find_problems(rects);
+    cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+    cr.set_font_size(11.0);
+
+    for (i, rect) in rects.iter().enumerate() {
+        let (x, y) = view.to_screen(rect.x, rect.y);
+        let (w, h) = (rect.width * view.zoom, rect.height * view.zoom);
+        let alpha = if dragging == Some(rect.row) {
+            0.95
+        } else {
+            0.75
+        };
+        cr.rectangle(x + 1.0, y + 1.0, w - 2.0, h - 2.0);
+        cr.set_source_rgba(0.21, 0.52, 0.89, alpha);
+        let _ = cr.fill_preserve();
+        if detached.contains(&i) {
+            cr.set_source_rgb(0.96, 0.62, 0.04);
+        } else {
+            cr.set_source_rgb(0.11, 0.35, 0.65);
+        }
+        cr.set_line_width(2.0);
+        cr.set_dash(if rect.auto { &[6.0, 4.0] } else { &[] }, 0.0);
+        let _ = cr.stroke();
+        cr.set_dash(&[], 0.0);
+
+        cr.save().ok();
+        cr.rectangle(x, y, w, h);
+        cr.clip();
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.move_to(x + 8.0, y + 18.0);
+        let _ = cr.show_text(&rect.name);
+        cr.move_to(x + 8.0, y + 32.0);
+        let _ = cr.show_text(&rect.mode);
+        cr.restore().ok();
+    }
+
+    cr.set_source_rgba(0.88, 0.11, 0.14, 0.5);
+    for (a, b) in overlaps {
+        let (a, b) = (&rects[a], &rects[b]);
+        let (x1, y1) = view.to_screen(a.x.max(b.x), a.y.max(b.y));
+        let (x2, y2) = view.to_screen(a.right().min(b.right()), a.bottom().min(b.bottom()));
+        cr.rectangle(x1, y1, x2 - x1, y2 - y1);
+        let _ = cr.fill();
+    }
+}
+
+/// Describe overlaps and outputs that don't connect to the rest.
+fn problem_text(rects: &[OutputRect]) -> String {
+    let (overlaps, detached) = find_problems(rects);
+    let mut lines: Vec<String> = overlaps
+        .iter()
+        .map(|(a, b)| format!("{} overlaps {}", rects[*a].name, rects[*b].name))
+        .collect();
+    lines.extend(detached.iter().map(|i| {
+        format!(
+            "{} isn't connected to the rest of the layout",
+            rects[*i].name
+        )
+    }));
+    lines.join("\n")
+}
+
+/// A canvas showing the monitor rows as rectangles that can be dragged into place,
+/// with a line below it naming overlapping and detached outputs.
+pub fn new(tables: &RuleTables) -> Box {
+    let container = Box::new(Orientation::Vertical, 4);
+    container.set_margin_top(10);
+    container.set_margin_start(10);
+    container.set_margin_end(10);
+
+    let area = DrawingArea::new();
+    area.set_content_height(220);
+    area.set_hexpand(true);
+    area.set_tooltip_text(Some(
+        "Drag outputs to arrange them. Dashed outputs are placed automatically.",
+    ));
+    container.append(&area);
+
+    let problems = Label::new(None);
+    problems.set_halign(gtk::Align::Start);
+    problems.add_css_class("error");
+    problems.set_visible(false);
+    container.append(&problems);
+
+    // The view is kept while dragging, so the layout doesn't rescale under the pointer
+    let view = Rc::new(Cell::new(None::<View>));
+    let drag = Rc::new(Cell::new(None::<Drag>));
+
+    let tables_clone = tables.clone();
+    let view_clone = view.clone();
+    let drag_clone = drag.clone();
+    area.set_draw_func(move |_, cr, width, height| {
+        let rects = tables_clone.output_rects();
+        let current = match (drag_clone.get(), view_clone.get()) {
+            (Some(_), Some(view)) => view,
+            _ => View::fit(&rects, width as f64, height as f64),
+        };
+        view_clone.set(Some(current));
+        draw(cr, &rects, current, drag_clone.get().map(|d| d.row));
+    });
+
+    let gesture = gtk::GestureDrag::new();
+    let tables_clone = tables.clone();
+    let view_clone = view.clone();
+    let drag_clone = drag.clone();
+    gesture.connect_drag_begin(move |_, x, y| {
+        let Some(view) = view_clone.get() else {
+            return;
+        };
+        let (lx, ly) = view.to_layout(x, y);
+        // Topmost first, matching the drawing order
+        let hit = tables_clone
+            .output_rects()
+            .into_iter()
+            .rev()
+            .find(|r| lx >= r.x && lx < r.right() && ly >= r.y && ly < r.bottom());
+        drag_clone.set(hit.map(|r| Drag {
+            row: r.row,
+            start_x: r.x,
+            start_y: r.y,
+        }));
+    });
+
+    let tables_clone = tables.clone();
+    let view_clone = view.clone();
+    let drag_clone = drag.clone();
+    gesture.connect_drag_update(move |_, dx, dy| {
+        let (Some(current), Some(view)) = (drag_clone.get(), view_clone.get()) else {
+            return;
+        };
+        let rects = tables_clone.output_rects();
+        let Some(mut moving) = rects.iter().find(|r| r.row == current.row).cloned() else {
+            return;
+        };
+        moving.x = current.start_x + dx / view.zoom;
+        moving.y = current.start_y + dy / view.zoom;
+        let (x, y) = snap(&rects, &moving, SNAP_DISTANCE / view.zoom);
+        tables_clone.set_position(current.row, x.round() as i32, y.round() as i32);
+    });
+
+    gesture.connect_drag_end(move |gesture, _, _| {
+        drag.set(None);
+        if let Some(area) = gesture.widget() {
+            area.queue_draw();
+        }
+    });
+    area.add_controller(gesture);
+
+    // Weak, since the tables outlive neither widget
+    let area_weak = area.downgrade();
+    let problems_weak = problems.downgrade();
+    *tables.layout_listener.borrow_mut() = Some(std::boxed::Box::new(move |tables| {
+        let (Some(area), Some(problems)) = (area_weak.upgrade(), problems_weak.upgrade()) else {
+            return;
+        };
+        let text = problem_text(&tables.output_rects());
+        problems.set_visible(!text.is_empty());
+        problems.set_text(&text);
+        area.queue_draw();
+    }));
+
+    container
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(row: usize, x: f64, y: f64) -> OutputRect {
+        OutputRect {
+            row,
+            name: format!("DP-{}", row + 1),
+            mode: String::new(),
+            x,
+            y,
+            width: 1920.0,
+            height: 1080.0,
+            auto: false,
+        }
+    }
+
+    #[test]
+    fn flags_groups_apart_from_the_largest() {
+        let rects = [
+            rect(0, 0.0, 0.0),
+            rect(1, 1920.0, 0.0),
+            rect(2, 3840.0, 0.0),
+            rect(3, 10000.0, 0.0),
+            rect(4, 11920.0, 0.0),
+        ];
+        let (overlaps, detached) = find_problems(&rects);
+        assert!(overlaps.is_empty());
+        assert_eq!(detached, vec![3, 4]);
+    }
+
+    #[test]
+    fn reports_overlaps_as_connected() {
+        let rects = [rect(0, 0.0, 0.0), rect(1, 1000.0, 0.0)];
+        let (overlaps, detached) = find_problems(&rects);
+        assert_eq!(overlaps, vec![(0, 1)]);
+        assert!(detached.is_empty());
+    }
+}
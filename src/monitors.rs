@@ -0,0 +1,1677 @@
+use gtk::{Box, DropDown, Entry, ListBox, Orientation, Switch, glib, prelude::*};
+
+use hyprparser::HyprlandConfig;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::config_lines::{lines, lines_mut, replace_value};
+use crate::drm;
+use crate::ipc::{self, HyprlandRequests, Monitor, SocketClient};
+use crate::monitorv2;
+use crate::rule_fields::RuleFields;
+use crate::scales;
+use crate::widgets::{notify_changes, set_conflict, set_modified};
+
+pub const MONITOR_KEYWORD: &str = "monitor";
+pub const WORKSPACE_KEYWORD: &str = "workspace";
+
+/// Where a `monitor=`/`workspace=` line was read from, so it can be found again on save.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleOrigin {
+    /// `None` for the main config file, otherwise an index into `sourced_content`.
+    pub source: Option<usize>,
+    /// The trimmed line as it currently exists in that file, or for a `monitorv2` block
+    /// its trimmed lines joined by newlines.
+    pub line: String,
+    pub block: bool,
+    /// The line it was read from, which tells identical rules in one file apart.
+    pub index: usize,
+}
+
+impl RuleOrigin {
+    /// The `monitor=` value this origin holds, converted from block form if needed.
+    fn monitor_value(&self) -> Option<String> {
+        if self.block {
+            Some(monitorv2::block_value(&self.line))
+        } else {
+            rule_value(&self.line, MONITOR_KEYWORD)
+        }
+    }
+
+    /// `monitor` or `workspace`, depending on the rule this origin holds.
+    fn keyword(&self) -> &'static str {
+        if self.monitor_value().is_some() {
+            MONITOR_KEYWORD
+        } else {
+            WORKSPACE_KEYWORD
+        }
+    }
+
+    /// The rule value this origin holds.
+    fn value(&self) -> String {
+        self.monitor_value()
+            .or_else(|| rule_value(&self.line, WORKSPACE_KEYWORD))
+            .unwrap_or_default()
+    }
+}
+
+pub struct MonitorRow {
+    row: Box,
+    origin: Option<RuleOrigin>,
+    saved: String,
+    name: DropDown,
+    mode: DropDown,
+    position: Entry,
+    scale: Entry,
+    fields: RuleFields,
+    /// Everything after the mode of `disable` and `addreserved` rules, kept verbatim.
+    tail: Vec<String>,
+}
+
+impl MonitorRow {
+    /// The rule value as it should be written after `monitor=`.
+    pub fn rule(&self) -> String {
+        let name = rule_name(&self.name);
+        let mode = selected_string(&self.mode).unwrap_or_else(|| "preferred".to_string());
+        let mut fields = vec![name, mode.clone()];
+        if is_special_mode(&mode) {
+            fields.extend(self.tail.iter().cloned());
+        } else {
+            fields.push(self.position.text().trim().to_string());
+            fields.push(self.scale.text().trim().to_string());
+            fields.extend(self.fields.render());
+        }
+        fields.join(",")
+    }
+
+    /// Show `value` in the row's widgets and return the verbatim tail to keep.
+    fn show_rule(&self, data: &MonitorData, value: &str) -> Vec<String> {
+        let parts: Vec<String> = value.split(',').map(|p| p.trim().to_string()).collect();
+        let field = |i: usize| parts.get(i).map(|s| s.as_str()).filter(|s| !s.is_empty());
+        let name = parts.first().map(|s| s.as_str()).unwrap_or_default();
+        set_strings(&self.name, data.monitor_choices(), Some(shown_name(name)));
+        let mode = field(1).unwrap_or("preferred");
+        set_strings(&self.mode, data.modes_for(Some(name)), Some(mode));
+        if is_special_mode(mode) {
+            self.position.set_text("auto");
+            self.scale.set_text("1");
+            self.fields.load(&[]);
+            return parts.get(2..).map(|p| p.to_vec()).unwrap_or_default();
+        }
+        self.position.set_text(field(2).unwrap_or("auto"));
+        self.scale.set_text(field(3).unwrap_or("1"));
+        self.fields
+            .load(parts.get(4..).map(|p| p.to_vec()).as_deref().unwrap_or(&[]));
+        Vec::new()
+    }
+}
+
+/// Modes that make a rule something other than `NAME,MODE,POS,SCALE[,key,value...]`.
+fn is_special_mode(mode: &str) -> bool {
+    matches!(mode, "disable" | "disabled" | "addreserved")
+}
+
+pub struct WorkspaceRow {
+    row: Box,
+    origin: Option<RuleOrigin>,
+    saved: String,
+    /// `1`, `name:web`, `special:scratch` or a selector such as `r[1-5]`.
+    workspace: Entry,
+    monitor: DropDown,
+    default: Switch,
+    fields: RuleFields,
+}
+
+impl WorkspaceRow {
+    /// The rule value as it should be written after `workspace=`.
+    pub fn rule(&self) -> String {
+        let mut fields = vec![self.workspace.text().trim().to_string()];
+        if let Some(monitor) = selected_string(&self.monitor)
+            && monitor != NO_MONITOR
+        {
+            fields.push(format!("monitor:{}", monitor));
+        }
+        if self.default.is_active() {
+            fields.push("default:true".to_string());
+        }
+        // The command takes the rest of the rule, so nothing may follow it
+        let (command, other): (Vec<String>, Vec<String>) = self
+            .fields
+            .render()
+            .into_iter()
+            .partition(|field| field.starts_with("on-created-empty:"));
+        fields.extend(other);
+        fields.extend(command);
+        fields.join(",")
+    }
+
+    /// Show `value` in the row's widgets.
+    fn show_rule(&self, data: &MonitorData, value: &str) {
+        let rule = WorkspaceRule::parse(value, false);
+        self.workspace.set_text(&rule.workspace);
+        set_strings(
+            &self.monitor,
+            data.workspace_monitor_choices(),
+            Some(rule.monitor.as_deref().unwrap_or(NO_MONITOR)),
+        );
+        self.default.set_active(rule.default);
+        self.fields.load(&rule.extra);
+    }
+}
+
+/// The parts of a `workspace=` rule the row shows in their own widgets.
+struct WorkspaceRule {
+    workspace: String,
+    monitor: Option<String>,
+    default: bool,
+    extra: Vec<String>,
+}
+
+impl WorkspaceRule {
+    /// Split `WORKSPACE,monitor:NAME,default:true[,rule:value...]`, with `default` as
+    /// the default state when the rule doesn't say.
+    fn parse(value: &str, default: bool) -> Self {
+        let mut rule = WorkspaceRule {
+            workspace: "1".to_string(),
+            monitor: None,
+            default,
+            extra: Vec::new(),
+        };
+        for (i, p) in workspace_fields(value).into_iter().enumerate() {
+            if i == 0 {
+                if !p.is_empty() {
+                    rule.workspace = p.to_string();
+                }
+            } else if let Some(name) = p.strip_prefix("monitor:") {
+                rule.monitor = Some(name.to_string());
+            } else if let Some(def) = p.strip_prefix("default:") {
+                rule.default = def == "true";
+            } else if !p.is_empty() {
+                rule.extra.push(p.to_string());
+            }
+        }
+        rule
+    }
+}
+
+/// Split a `workspace=` rule at commas, except for the command of `on-created-empty:`,
+/// which runs to the end of the rule the way Hyprland reads it.
+fn workspace_fields(value: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut rest = value;
+    loop {
+        if !fields.is_empty() && rest.trim_start().starts_with("on-created-empty:") {
+            fields.push(rest.trim());
+            return fields;
+        }
+        match rest.split_once(',') {
+            Some((field, tail)) => {
+                fields.push(field.trim());
+                rest = tail;
+            }
+            None => {
+                fields.push(rest.trim());
+                return fields;
+            }
+        }
+    }
+}
+
+/// Edited, added and deleted rows, to carry over to the rows read after the files were
+/// reloaded.
+#[derive(Clone, Default)]
+pub struct TableEdits {
+    /// Edited and new monitor rows: where the rule was read from and its value now.
+    monitors: Vec<(Option<RuleOrigin>, String)>,
+    workspaces: Vec<(Option<RuleOrigin>, String)>,
+    removed: Vec<RuleOrigin>,
+}
+
+/// How an edit made before a reload carries over to the rules read afterwards.
+enum Replay {
+    /// Show `rule` in the reloaded row at `row`, or in a new row when there is none.
+    Edit {
+        row: Option<usize>,
+        origin: Option<RuleOrigin>,
+        rule: String,
+        conflict: bool,
+    },
+    /// Delete the reloaded row, or only flag it when it changed on disk.
+    Delete { row: usize, conflict: bool },
+}
+
+impl Replay {
+    fn conflict(&self) -> bool {
+        match self {
+            Replay::Edit { conflict, .. } | Replay::Delete { conflict, .. } => *conflict,
+        }
+    }
+}
+
+impl TableEdits {
+    pub fn is_empty(&self) -> bool {
+        self.monitors.is_empty() && self.workspaces.is_empty() && self.removed.is_empty()
+    }
+
+    /// Edits to rules whose line changed on disk, once `config` is loaded.
+    pub fn conflicts(&self, config: &HyprlandConfig) -> usize {
+        [MONITOR_KEYWORD, WORKSPACE_KEYWORD]
+            .into_iter()
+            .map(|keyword| {
+                let rules: Vec<(Option<RuleOrigin>, String)> = collect_rules(config, keyword)
+                    .into_iter()
+                    .map(|(origin, value)| (Some(origin), value))
+                    .collect();
+                self.plan(keyword, &rules)
+                    .iter()
+                    .filter(|step| step.conflict())
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Match the `keyword` edits to the reloaded `rules`. An edit lands on the rule read
+    /// from the same line, then on one with the same text that moved, or else on the one
+    /// for the same output or workspace in the same file, which means that rule changed
+    /// on disk too.
+    fn plan(&self, keyword: &str, rules: &[(Option<RuleOrigin>, String)]) -> Vec<Replay> {
+        let mut claimed = HashSet::new();
+        let mut counterpart = |origin: &RuleOrigin| {
+            let unclaimed = || {
+                rules
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, _)| !claimed.contains(idx))
+            };
+            let same_rule = |o: &RuleOrigin| {
+                o.source == origin.source && o.line == origin.line && o.block == origin.block
+            };
+            let found = unclaimed()
+                .find(|(_, (o, _))| o.as_ref() == Some(origin))
+                .or_else(|| unclaimed().find(|(_, (o, _))| o.as_ref().is_some_and(same_rule)))
+                .map(|(idx, _)| (idx, false))
+                .or_else(|| {
+                    let name = origin.value();
+                    unclaimed()
+                        .find(|(_, (o, value))| {
+                            o.as_ref().is_some_and(|o| o.source == origin.source)
+                                && first_field(value) == first_field(&name)
+                        })
+                        .map(|(idx, _)| (idx, true))
+                });
+            if let Some((idx, _)) = found {
+                claimed.insert(idx);
+            }
+            found
+        };
+
+        let edits = if keyword == MONITOR_KEYWORD {
+            &self.monitors
+        } else {
+            &self.workspaces
+        };
+        let mut steps = Vec::new();
+        for (origin, rule) in edits {
+            let found = origin.as_ref().and_then(&mut counterpart);
+            steps.push(Replay::Edit {
+                row: found.map(|(idx, _)| idx),
+                origin: origin.clone(),
+                rule: rule.clone(),
+                // Rows read from a line that is gone from the file come back as new ones
+                conflict: origin.is_some() && found.is_none_or(|(_, conflict)| conflict),
+            });
+        }
+        for origin in self.removed.iter().filter(|o| o.keyword() == keyword) {
+            if let Some((row, conflict)) = counterpart(origin) {
+                steps.push(Replay::Delete { row, conflict });
+            }
+        }
+        steps
+    }
+}
+
+pub type LayoutListener = std::boxed::Box<dyn Fn(&RuleTables)>;
+
+/// Rows of the Monitors page tables plus the lines deleted since the last save.
+#[derive(Clone, Default)]
+pub struct RuleTables {
+    pub monitors: Rc<RefCell<Vec<MonitorRow>>>,
+    pub workspaces: Rc<RefCell<Vec<WorkspaceRow>>>,
+    pub removed: Rc<RefCell<Vec<RuleOrigin>>>,
+    /// Names and modes the dropdowns offer, replaced when outputs come and go.
+    pub monitor_data: Rc<RefCell<MonitorData>>,
+    /// The monitor table as last loaded or confirmed after "Apply now".
+    confirmed: Rc<RefCell<MonitorSnapshot>>,
+    /// Called whenever a row changes, to keep the arrangement canvas in sync.
+    pub layout_listener: Rc<RefCell<Option<LayoutListener>>>,
+    /// Edits made before the files were reloaded, applied once the rows are filled in.
+    pub replay: Rc<RefCell<Option<TableEdits>>>,
+}
+
+/// Where a monitor row puts its output, in logical pixels.
+#[derive(Clone, Debug)]
+pub struct OutputRect {
+    /// Index into [`RuleTables::monitors`].
+    pub row: usize,
+    pub name: String,
+    pub mode: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// The row has no explicit position, so Hyprland places it.
+    pub auto: bool,
+}
+
+impl OutputRect {
+    pub fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+}
+
+/// Monitor rows and deleted `monitor=` lines, to go back to when a layout is rejected.
+#[derive(Clone, Default, PartialEq)]
+struct MonitorSnapshot {
+    rows: Vec<(Option<RuleOrigin>, String)>,
+    removed: Vec<RuleOrigin>,
+}
+
+impl RuleTables {
+    pub fn is_modified(&self) -> bool {
+        !self.removed.borrow().is_empty()
+            || self
+                .monitors
+                .borrow()
+                .iter()
+                .any(|r| r.origin.is_none() || r.rule() != r.saved)
+            || self
+                .workspaces
+                .borrow()
+                .iter()
+                .any(|r| r.origin.is_none() || r.rule() != r.saved)
+    }
+
+    /// Mirror the tables into `changed_options` so the save path picks them up.
+    pub fn record_changes(
+        &self,
+        changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+        list: &ListBox,
+    ) {
+        let monitors_key = ("monitors".to_string(), MONITOR_KEYWORD.to_string());
+        let workspaces_key = ("monitors".to_string(), WORKSPACE_KEYWORD.to_string());
+        let mut changes = changed_options.borrow_mut();
+        if self.is_modified() {
+            let monitors: Vec<String> = self.monitors.borrow().iter().map(|r| r.rule()).collect();
+            let workspaces: Vec<String> =
+                self.workspaces.borrow().iter().map(|r| r.rule()).collect();
+            changes.insert(monitors_key, monitors.join("\n"));
+            changes.insert(workspaces_key, workspaces.join("\n"));
+        } else {
+            changes.remove(&monitors_key);
+            changes.remove(&workspaces_key);
+        }
+        drop(changes);
+
+        for row in self.monitors.borrow().iter() {
+            set_modified(&row.row, row.origin.is_none() || row.rule() != row.saved);
+        }
+        for row in self.workspaces.borrow().iter() {
+            set_modified(&row.row, row.origin.is_none() || row.rule() != row.saved);
+        }
+        self.layout_changed();
+        notify_changes(list);
+    }
+
+    pub fn layout_changed(&self) {
+        if let Some(listener) = self.layout_listener.borrow().as_ref() {
+            listener(self);
+        }
+    }
+
+    /// The outputs the monitor rows describe, sized by mode, scale and transform. Rows
+    /// without a position are lined up to the right, like Hyprland's `auto`.
+    pub fn output_rects(&self) -> Vec<OutputRect> {
+        let data = self.monitor_data.borrow();
+        let mut rects: Vec<OutputRect> = Vec::new();
+        for (idx, row) in self.monitors.borrow().iter().enumerate() {
+            let name = rule_name(&row.name);
+            let mode = selected_string(&row.mode).unwrap_or_default();
+            if name.is_empty() || is_special_mode(&mode) {
+                continue;
+            }
+            let (mut width, mut height) = parse_resolution(&mode)
+                .or_else(|| data.sizes.get(&name).copied())
+                .unwrap_or((1920, 1080));
+            if row.fields.transform() % 2 == 1 {
+                std::mem::swap(&mut width, &mut height);
+            }
+            let scale = row
+                .scale
+                .text()
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|s| *s > 0.0)
+                .unwrap_or(1.0);
+            let position = parse_position(row.position.text().trim());
+            rects.push(OutputRect {
+                row: idx,
+                name,
+                mode,
+                x: position.map_or(0.0, |p| p.0 as f64),
+                y: position.map_or(0.0, |p| p.1 as f64),
+                width: (width as f64 / scale).round(),
+                height: (height as f64 / scale).round(),
+                auto: position.is_none(),
+            });
+        }
+
+        let mut next_x = rects
+            .iter()
+            .filter(|r| !r.auto)
+            .map(|r| r.right())
+            .fold(0.0, f64::max);
+        for rect in rects.iter_mut().filter(|r| r.auto) {
+            rect.x = next_x;
+            next_x += rect.width;
+        }
+        rects
+    }
+
+    /// Set the position entry of a monitor row, e.g. after dragging it on the canvas.
+    pub fn set_position(&self, row: usize, x: i32, y: i32) {
+        let entry = self.monitors.borrow().get(row).map(|r| r.position.clone());
+        if let Some(entry) = entry {
+            entry.set_text(&format!("{}x{}", x, y));
+        }
+    }
+
+    /// The `monitor=` rule values currently in the table.
+    pub fn monitor_rules(&self) -> Vec<String> {
+        self.monitors.borrow().iter().map(|r| r.rule()).collect()
+    }
+
+    /// Monitor and workspace rules of the rows read from `source`, plus rows not saved yet.
+    pub fn rules_from(&self, source: Option<usize>) -> (Vec<String>, Vec<String>) {
+        let keep = |origin: &Option<RuleOrigin>| origin.as_ref().is_none_or(|o| o.source == source);
+        (
+            self.monitors
+                .borrow()
+                .iter()
+                .filter(|r| keep(&r.origin))
+                .map(|r| r.rule())
+                .collect(),
+            self.workspaces
+                .borrow()
+                .iter()
+                .filter(|r| keep(&r.origin))
+                .map(|r| r.rule())
+                .collect(),
+        )
+    }
+
+    /// The row of the monitor or workspace rule on line `idx` of `source`, whose content
+    /// is `lines`.
+    pub fn row_at(&self, source: Option<usize>, lines: &[String], idx: usize) -> Option<Box> {
+        let holds = |origin: &Option<RuleOrigin>| {
+            origin.as_ref().is_some_and(|o| {
+                o.source == source
+                    && locate(lines, o, &HashSet::new()).is_some_and(|range| range.contains(&idx))
+            })
+        };
+        let monitor = self
+            .monitors
+            .borrow()
+            .iter()
+            .find(|r| holds(&r.origin))
+            .map(|r| r.row.clone());
+        monitor.or_else(|| {
+            self.workspaces
+                .borrow()
+                .iter()
+                .find(|r| holds(&r.origin))
+                .map(|r| r.row.clone())
+        })
+    }
+
+    /// The rows edited, added and deleted since the tables were loaded. Edits still
+    /// waiting to be replayed are handed over instead, and won't be applied here anymore.
+    pub fn pending_edits(&self) -> TableEdits {
+        if let Some(edits) = self.replay.borrow_mut().take() {
+            return edits;
+        }
+        let edited = |origin: &Option<RuleOrigin>, rule: String, saved: &str| {
+            (origin.is_none() || rule != saved).then(|| (origin.clone(), rule))
+        };
+        TableEdits {
+            monitors: self
+                .monitors
+                .borrow()
+                .iter()
+                .filter_map(|r| edited(&r.origin, r.rule(), &r.saved))
+                .collect(),
+            workspaces: self
+                .workspaces
+                .borrow()
+                .iter()
+                .filter_map(|r| edited(&r.origin, r.rule(), &r.saved))
+                .collect(),
+            removed: self.removed.borrow().clone(),
+        }
+    }
+
+    /// Apply the edits waiting in [`Self::replay`] to the rows just read from the files.
+    /// Edits to rules that changed on disk meanwhile are applied too, but flagged.
+    pub fn replay_edits(
+        &self,
+        monitor_list: &ListBox,
+        workspace_list: &ListBox,
+        changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+    ) {
+        let Some(edits) = self.replay.borrow_mut().take() else {
+            return;
+        };
+        let data = self.monitor_data.borrow().clone();
+        for (keyword, list) in [
+            (MONITOR_KEYWORD, monitor_list),
+            (WORKSPACE_KEYWORD, workspace_list),
+        ] {
+            let rows = self.rows(keyword);
+            let rules: Vec<(Option<RuleOrigin>, String)> = rows
+                .iter()
+                .map(|(origin, saved, _)| (origin.clone(), saved.clone()))
+                .collect();
+            let mut deleted = Vec::new();
+            for step in edits.plan(keyword, &rules) {
+                match step {
+                    Replay::Edit {
+                        row,
+                        origin,
+                        rule,
+                        conflict,
+                    } => {
+                        let idx = match row {
+                            Some(idx) => idx,
+                            None => {
+                                let saved = origin.map(|o| {
+                                    let value = o.value();
+                                    (o, value)
+                                });
+                                if keyword == MONITOR_KEYWORD {
+                                    add_monitor_row(list, saved, self, changed_options);
+                                } else {
+                                    add_workspace_row(list, saved, self, changed_options);
+                                }
+                                self.rows(keyword).len() - 1
+                            }
+                        };
+                        let Some(widget) = self.show_rule_at(keyword, idx, &data, &rule) else {
+                            continue;
+                        };
+                        if conflict {
+                            set_conflict(
+                                &widget,
+                                &match row {
+                                    Some(idx) => format!(
+                                        "Also changed on disk to \"{}\". Saving keeps your rule.",
+                                        rows[idx].1
+                                    ),
+                                    None => "Removed from the file on disk. Saving adds it back."
+                                        .to_string(),
+                                },
+                            );
+                        }
+                    }
+                    Replay::Delete {
+                        row,
+                        conflict: false,
+                    } => deleted.push(rows[row].2.clone()),
+                    Replay::Delete {
+                        row,
+                        conflict: true,
+                    } => set_conflict(
+                        &rows[row].2,
+                        &format!(
+                            "Changed on disk to \"{}\" after you deleted it. Delete it again to drop it.",
+                            rows[row].1
+                        ),
+                    ),
+                }
+            }
+            for row in deleted {
+                self.remove_row(list, &row);
+            }
+        }
+        self.record_changes(changed_options, monitor_list);
+    }
+
+    /// Origin, rule as read and widget of each `keyword` row.
+    fn rows(&self, keyword: &str) -> Vec<(Option<RuleOrigin>, String, Box)> {
+        if keyword == MONITOR_KEYWORD {
+            self.monitors
+                .borrow()
+                .iter()
+                .map(|r| (r.origin.clone(), r.saved.clone(), r.row.clone()))
+                .collect()
+        } else {
+            self.workspaces
+                .borrow()
+                .iter()
+                .map(|r| (r.origin.clone(), r.saved.clone(), r.row.clone()))
+                .collect()
+        }
+    }
+
+    /// Show `rule` in the `keyword` row at `idx` and return its widget.
+    fn show_rule_at(
+        &self,
+        keyword: &str,
+        idx: usize,
+        data: &MonitorData,
+        rule: &str,
+    ) -> Option<Box> {
+        if keyword == WORKSPACE_KEYWORD {
+            let workspaces = self.workspaces.borrow();
+            let row = workspaces.get(idx)?;
+            row.show_rule(data, rule);
+            return Some(row.row.clone());
+        }
+        // Showing the rule notifies, which reads the rows, so the tail is set afterwards
+        let tail = self.monitors.borrow().get(idx)?.show_rule(data, rule);
+        let mut monitors = self.monitors.borrow_mut();
+        let row = monitors.get_mut(idx)?;
+        row.tail = tail;
+        Some(row.row.clone())
+    }
+
+    /// Drop the row holding `row` from its table and list, remembering its line so the
+    /// save deletes it.
+    fn remove_row(&self, list: &ListBox, row: &Box) {
+        let mut removed = Vec::new();
+        self.monitors.borrow_mut().retain(|r| {
+            if r.row == *row {
+                removed.extend(r.origin.clone());
+                false
+            } else {
+                true
+            }
+        });
+        self.workspaces.borrow_mut().retain(|r| {
+            if r.row == *row {
+                removed.extend(r.origin.clone());
+                false
+            } else {
+                true
+            }
+        });
+        self.removed.borrow_mut().extend(removed);
+        if let Some(ancestor) = row.ancestor(gtk::ListBoxRow::static_type())
+            && let Some(lb_row) = ancestor.downcast_ref::<gtk::ListBoxRow>()
+        {
+            list.remove(lb_row);
+        }
+    }
+
+    /// Remember the monitor table as the layout to return to when an applied one is rejected.
+    pub fn mark_confirmed(&self) {
+        *self.confirmed.borrow_mut() = self.monitor_snapshot();
+    }
+
+    /// Whether the monitor table is as loaded or as last kept after "Apply now". Only
+    /// such layouts are saved while a compositor can preview them.
+    pub fn is_confirmed(&self) -> bool {
+        *self.confirmed.borrow() == self.monitor_snapshot()
+    }
+
+    fn monitor_snapshot(&self) -> MonitorSnapshot {
+        MonitorSnapshot {
+            rows: self
+                .monitors
+                .borrow()
+                .iter()
+                .map(|r| (r.origin.clone(), r.rule()))
+                .collect(),
+            removed: self
+                .removed
+                .borrow()
+                .iter()
+                .filter(|o| o.monitor_value().is_some())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Rebuild the monitor rows as they were at the last [`Self::mark_confirmed`].
+    fn restore_confirmed(
+        &self,
+        list: &ListBox,
+        changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+    ) {
+        let snapshot = self.confirmed.borrow().clone();
+        while let Some(row) = list.first_child() {
+            list.remove(&row);
+        }
+        self.monitors.borrow_mut().clear();
+        self.removed
+            .borrow_mut()
+            .retain(|o| o.monitor_value().is_none());
+        self.removed.borrow_mut().extend(snapshot.removed);
+
+        let data = self.monitor_data.borrow().clone();
+        for (origin, rule) in snapshot.rows {
+            // Rows from a file start from their line, so they still compare against it
+            let saved = origin
+                .as_ref()
+                .map(|o| (o.clone(), o.monitor_value().unwrap_or_default()));
+            add_monitor_row(list, saved, self, changed_options);
+            let tail = self
+                .monitors
+                .borrow()
+                .last()
+                .map(|row| row.show_rule(&data, &rule));
+            if let (Some(row), Some(tail)) = (self.monitors.borrow_mut().last_mut(), tail) {
+                row.tail = tail;
+            }
+        }
+        self.record_changes(changed_options, list);
+    }
+
+    /// Offer the names and modes of `data` in every row, keeping what each row has selected
+    /// even when that output is gone.
+    pub fn refresh_monitors(&self, data: MonitorData) {
+        *self.monitor_data.borrow_mut() = data.clone();
+        for row in self.monitors.borrow().iter() {
+            let name = selected_string(&row.name);
+            let mode = selected_string(&row.mode);
+            set_strings(&row.name, data.monitor_choices(), name.as_deref());
+            // Changing the name resets the modes, so they are filled in afterwards
+            let modes = data.modes_for(Some(&rule_name(&row.name)));
+            set_strings(&row.mode, modes, mode.as_deref());
+            row.fields.set_monitor_names(&data.names);
+        }
+        for row in self.workspaces.borrow().iter() {
+            let monitor = selected_string(&row.monitor);
+            set_strings(
+                &row.monitor,
+                data.workspace_monitor_choices(),
+                monitor.as_deref(),
+            );
+        }
+        self.layout_changed();
+    }
+
+    /// Rewrite, remove and add `monitor=`/`workspace=` lines in the file each row came from.
+    /// New rows go to `new_rules`, the active profile's file so it keeps the whole layout,
+    /// or the main config. The rows themselves are left untouched; the page is reloaded
+    /// after a save.
+    pub fn apply(&self, config: &mut HyprlandConfig, new_rules: Option<usize>) {
+        // Find every line first and rewrite each file from its end, so the lines of one
+        // rule don't move while the others are looked up
+        type Change = (Range<usize>, bool, Option<String>);
+        let mut changes: HashMap<Option<usize>, Vec<Change>> = HashMap::new();
+        let mut added: Vec<(Option<usize>, &str, String)> = Vec::new();
+        let mut used: HashMap<Option<usize>, HashSet<usize>> = HashMap::new();
+        let mut change =
+            |origin: Option<&RuleOrigin>, keyword, rule: Option<String>| {
+                let found = origin.and_then(|origin| {
+                    let lines = lines(config, origin.source)?;
+                    let used = used.entry(origin.source).or_default();
+                    let range = locate(lines, origin, used)?;
+                    used.insert(range.start);
+                    Some((origin, range))
+                });
+                match (found, rule) {
+                    (Some((origin, range)), rule) => changes
+                        .entry(origin.source)
+                        .or_default()
+                        .push((range, origin.block, rule)),
+                    (None, Some(rule)) => {
+                        let source = origin.map_or(new_rules, |origin| origin.source);
+                        added.push((source, keyword, rule));
+                    }
+                    (None, None) => {}
+                }
+            };
+
+        for origin in self.removed.borrow().iter() {
+            change(Some(origin), MONITOR_KEYWORD, None);
+        }
+        for row in self.monitors.borrow().iter() {
+            let rule = row.rule();
+            if row.origin.is_none() || rule != row.saved {
+                change(row.origin.as_ref(), MONITOR_KEYWORD, Some(rule));
+            }
+        }
+        for row in self.workspaces.borrow().iter() {
+            let rule = row.rule();
+            if row.origin.is_none() || rule != row.saved {
+                change(row.origin.as_ref(), WORKSPACE_KEYWORD, Some(rule));
+            }
+        }
+
+        for (source, mut ranges) in changes {
+            let Some(lines) = lines_mut(config, source) else {
+                continue;
+            };
+            ranges.sort_by_key(|(range, ..)| std::cmp::Reverse(range.start));
+            for (range, block, rule) in ranges {
+                match rule {
+                    Some(rule) if block => {
+                        let block = monitorv2::update_block(&lines[range.clone()], &rule);
+                        lines.splice(range, block);
+                    }
+                    Some(rule) => lines[range.start] = replace_value(&lines[range.start], &rule),
+                    None => {
+                        lines.drain(range);
+                    }
+                }
+            }
+        }
+        for (source, keyword, rule) in added {
+            add_rule(config, source, keyword, &rule);
+        }
+    }
+}
+
+/// Monitor names and modes offered by the dropdowns.
+#[derive(Clone, Default)]
+pub struct MonitorData {
+    pub names: Vec<String>,
+    pub name_to_modes: HashMap<String, Vec<String>>,
+    pub all_modes: Vec<String>,
+    /// `desc:` identifiers of the outputs, which survive connector names changing.
+    pub descriptions: Vec<String>,
+    /// Current resolution of each output, for rows whose mode doesn't state one.
+    pub sizes: HashMap<String, (i32, i32)>,
+}
+
+impl MonitorData {
+    /// Monitor names and modes reported by the compositor, falling back to the names
+    /// used by the given `monitor=` rules.
+    pub fn from_monitors(monitors: &[Monitor], rules: &[String]) -> Self {
+        let mut data = MonitorData {
+            all_modes: vec!["preferred".to_string()],
+            ..Default::default()
+        };
+        for monitor in monitors {
+            if !data.names.contains(&monitor.name) {
+                data.names.push(monitor.name.clone());
+            }
+            data.sizes
+                .insert(monitor.name.clone(), (monitor.width, monitor.height));
+            let desc = description(monitor).map(|d| format!("desc:{}", d));
+            if let Some(desc) = &desc {
+                if !data.descriptions.contains(desc) {
+                    data.descriptions.push(desc.clone());
+                }
+                data.sizes
+                    .insert(desc.clone(), (monitor.width, monitor.height));
+            }
+            let mut modes_for_this: Vec<String> = Vec::new();
+            for mode in &monitor.available_modes {
+                let mode = mode.trim().to_string();
+                if !mode.is_empty() && !modes_for_this.contains(&mode) {
+                    modes_for_this.push(mode);
+                }
+            }
+            if !modes_for_this.is_empty() {
+                for mm in &modes_for_this {
+                    if !data.all_modes.contains(mm) {
+                        data.all_modes.push(mm.clone());
+                    }
+                }
+                if let Some(desc) = desc {
+                    data.name_to_modes.insert(desc, modes_for_this.clone());
+                }
+                data.name_to_modes
+                    .insert(monitor.name.clone(), modes_for_this);
+            }
+        }
+        if data.names.is_empty() {
+            // Fallback: infer names from existing lines
+            for rule in rules {
+                if let Some(name) = rule.split(',').next() {
+                    let name = name.trim();
+                    if !name.is_empty() && !data.names.contains(&name.to_string()) {
+                        data.names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        data
+    }
+
+    /// Connector names followed by `desc:` identifiers.
+    pub fn identifiers(&self) -> Vec<String> {
+        let mut identifiers = self.names.clone();
+        identifiers.extend(self.descriptions.iter().cloned());
+        identifiers
+    }
+
+    /// What a workspace can be bound to: any identifier, or no monitor at all.
+    fn workspace_monitor_choices(&self) -> Vec<String> {
+        let mut choices = vec![NO_MONITOR.to_string()];
+        choices.extend(self.identifiers());
+        choices
+    }
+
+    /// What a `monitor=` rule can name: any identifier, or every output at once.
+    fn monitor_choices(&self) -> Vec<String> {
+        let mut choices = self.identifiers();
+        choices.push(ANY_MONITOR.to_string());
+        choices
+    }
+
+    fn modes_for(&self, name: Option<&str>) -> Vec<String> {
+        let mut modes = vec!["preferred".to_string()];
+        match name.and_then(|n| self.name_to_modes.get(n)) {
+            Some(v) => modes.extend(v.clone()),
+            None => modes.extend(self.all_modes.clone()),
+        }
+        modes.push("disable".to_string());
+        let mut seen = HashSet::new();
+        modes.retain(|s| seen.insert(s.clone()));
+        modes
+    }
+}
+
+/// The `monitor=` rule that reproduces what an output currently runs, including the
+/// mirroring, adaptive sync, bit depth and color mode it reports.
+fn running_rule(monitor: &Monitor) -> String {
+    if monitor.disabled {
+        return format!("{},disable", monitor.name);
+    }
+    let mut fields = vec![
+        monitor.name.clone(),
+        format!(
+            "{}x{}@{:.2}",
+            monitor.width, monitor.height, monitor.refresh_rate
+        ),
+        format!("{}x{}", monitor.x, monitor.y),
+        monitor.scale.to_string(),
+        "transform".to_string(),
+        monitor.transform.to_string(),
+    ];
+    let mut field = |key: &str, value: String| {
+        fields.push(key.to_string());
+        fields.push(value);
+    };
+    if !monitor.mirror_of.is_empty() && monitor.mirror_of != "none" {
+        field("mirror", monitor.mirror_of.clone());
+    }
+    field("vrr", if monitor.vrr { "1" } else { "0" }.to_string());
+    if !monitor.current_format.is_empty() {
+        let bitdepth = if monitor.current_format.contains("2101010") {
+            "10"
+        } else {
+            "8"
+        };
+        field("bitdepth", bitdepth.to_string());
+    }
+    if !monitor.color_management_preset.is_empty() {
+        field("cm", monitor.color_management_preset.clone());
+        if monitor.color_management_preset.starts_with("hdr") {
+            field("sdrbrightness", format!("{:.2}", monitor.sdr_brightness));
+            field("sdrsaturation", format!("{:.2}", monitor.sdr_saturation));
+        }
+    }
+    fields.join(",")
+}
+
+/// How long a pushed layout stays without confirmation before it is reverted.
+const CONFIRM_SECONDS: u32 = 15;
+
+/// Push the monitor table to the running compositor and ask whether to keep it. Without
+/// an answer within [`CONFIRM_SECONDS`], the outputs and the table go back to how they were.
+pub fn apply_layout(
+    list: &ListBox,
+    tables: &RuleTables,
+    changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+    client: Option<Arc<SocketClient>>,
+) {
+    let window = list.root().and_downcast::<gtk::Window>();
+    let Some(client) = client else {
+        show_message(
+            window.as_ref(),
+            gtk::MessageType::Info,
+            "No Hyprland instance is selected, so the layout can't be applied.",
+        );
+        return;
+    };
+    let rules: Vec<(String, String)> = tables
+        .monitor_rules()
+        .into_iter()
+        .map(|rule| (MONITOR_KEYWORD.to_string(), rule))
+        .collect();
+    let list = list.clone();
+    let tables = tables.clone();
+    let changed_options = changed_options.clone();
+    glib::MainContext::default().spawn_local(async move {
+        let previous = match ipc::call(client.clone(), |c| c.monitors()).await {
+            Ok(monitors) => monitors
+                .iter()
+                .map(|m| (MONITOR_KEYWORD.to_string(), running_rule(m)))
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                show_message(
+                    window.as_ref(),
+                    gtk::MessageType::Error,
+                    &format!("Failed to query the current layout: {}", e),
+                );
+                return;
+            }
+        };
+        match ipc::call(client.clone(), move |c| c.keywords(&rules)).await {
+            Ok(errors) if errors.is_empty() => {}
+            result => {
+                let reason = match result {
+                    Ok(errors) => errors.join("\n"),
+                    Err(e) => e.to_string(),
+                };
+                // Some rules may have been taken before the failing one
+                let _ = ipc::call(client, move |c| c.keywords(&previous)).await;
+                show_message(
+                    window.as_ref(),
+                    gtk::MessageType::Error,
+                    &format!("Hyprland rejected the layout:\n{}", reason),
+                );
+                return;
+            }
+        }
+
+        let keep = confirm_layout(window.as_ref()).await;
+        if keep {
+            tables.mark_confirmed();
+        } else {
+            if let Err(e) = ipc::call(client, move |c| c.keywords(&previous)).await {
+                eprintln!("Failed to restore the monitor layout: {}", e);
+            }
+            tables.restore_confirmed(&list, &changed_options);
+        }
+    });
+}
+
+/// Show the "Keep this configuration?" countdown; `false` when reverted or timed out.
+async fn confirm_layout(window: Option<&gtk::Window>) -> bool {
+    let text = |left: u32| format!("The previous layout is restored in {} seconds.", left);
+    let dialog = gtk::MessageDialog::builder()
+        .message_type(gtk::MessageType::Question)
+        .title("Keep Layout")
+        .text("Keep this configuration?")
+        .secondary_text(text(CONFIRM_SECONDS))
+        .modal(true)
+        .build();
+    dialog.set_transient_for(window);
+    dialog.add_button("Revert", gtk::ResponseType::Reject);
+    dialog.add_button("Keep", gtk::ResponseType::Accept);
+    dialog.set_default_response(gtk::ResponseType::Reject);
+
+    let left = Rc::new(Cell::new(CONFIRM_SECONDS));
+    let left_clone = left.clone();
+    let dialog_clone = dialog.clone();
+    let countdown = glib::timeout_add_seconds_local(1, move || {
+        let left = &left_clone;
+        left.set(left.get().saturating_sub(1));
+        if left.get() == 0 {
+            dialog_clone.response(gtk::ResponseType::Reject);
+            return glib::ControlFlow::Break;
+        }
+        dialog_clone.set_secondary_text(Some(&text(left.get())));
+        glib::ControlFlow::Continue
+    });
+
+    let response = dialog.run_future().await;
+    dialog.close();
+    // Answered before the countdown ran out, so its source is still attached
+    if left.get() > 0 {
+        countdown.remove();
+    }
+    response == gtk::ResponseType::Accept
+}
+
+fn show_message(window: Option<&gtk::Window>, kind: gtk::MessageType, text: &str) {
+    let dialog = gtk::MessageDialog::builder()
+        .message_type(kind)
+        .title("Monitor Layout")
+        .text(text)
+        .buttons(gtk::ButtonsType::Ok)
+        .modal(true)
+        .build();
+    dialog.set_transient_for(window);
+    dialog.connect_response(|dialog, _| dialog.close());
+    dialog.show();
+}
+
+/// Outputs reported by the compositor of `client`, or read from DRM sysfs under `drm_root`
+/// when there is none or it doesn't answer.
+pub async fn connected_monitors(
+    client: Option<Arc<SocketClient>>,
+    drm_root: &Path,
+) -> Vec<Monitor> {
+    if let Some(client) = client {
+        match ipc::call(client, |c| c.monitors()).await {
+            Ok(monitors) => return monitors,
+            Err(e) => eprintln!("Failed to query monitors: {}", e),
+        }
+    }
+    drm::monitors(drm_root)
+}
+
+/// The description a `desc:` rule matches, if the output reports one.
+pub fn description(monitor: &Monitor) -> Option<String> {
+    // Older Hyprland versions append the connector to the description
+    let description = monitor
+        .description
+        .strip_suffix(&format!(" ({})", monitor.name))
+        .unwrap_or(&monitor.description)
+        .trim();
+    (!description.is_empty()).then(|| description.to_string())
+}
+
+/// Collect the `keyword=` rule values from the main and sourced files along with their
+/// origin. For `monitor`, `monitorv2` blocks are included as rules in file order.
+pub fn collect_rules(config: &HyprlandConfig, keyword: &str) -> Vec<(RuleOrigin, String)> {
+    let files = std::iter::once((None, &config.content)).chain(
+        config
+            .sourced_content
+            .iter()
+            .enumerate()
+            .map(|(idx, lines)| (Some(idx), lines)),
+    );
+    let mut rules = Vec::new();
+    for (source, lines) in files {
+        let mut found: Vec<(usize, RuleOrigin, String)> = Vec::new();
+        for (idx, ln) in lines.iter().enumerate() {
+            if let Some(value) = rule_value(ln, keyword) {
+                let origin = RuleOrigin {
+                    source,
+                    line: ln.trim().to_string(),
+                    block: false,
+                    index: idx,
+                };
+                found.push((idx, origin, value));
+            }
+        }
+        if keyword == MONITOR_KEYWORD {
+            for (start, key, value) in monitorv2::parse_blocks(lines) {
+                let origin = RuleOrigin {
+                    source,
+                    line: key,
+                    block: true,
+                    index: start,
+                };
+                found.push((start, origin, value));
+            }
+            found.sort_by_key(|(idx, _, _)| *idx);
+        }
+        rules.extend(found.into_iter().map(|(_, origin, value)| (origin, value)));
+    }
+    rules
+}
+
+pub fn add_monitor_row(
+    list: &ListBox,
+    rule: Option<(RuleOrigin, String)>,
+    tables: &RuleTables,
+    changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+) {
+    // monitor=NAME,MODE,POS,SCALE[,key,value...], or NAME,disable / NAME,addreserved,...
+    let (origin, value) = match rule {
+        Some((origin, value)) => (Some(origin), Some(value)),
+        None => (None, None),
+    };
+
+    let data = tables.monitor_data.borrow().clone();
+    let row = Box::new(Orientation::Horizontal, 6);
+    let dd = string_dropdown(&data.monitor_choices());
+    dd.set_width_request(140);
+    dd.set_halign(gtk::Align::Start);
+    let dd_mode = string_dropdown(&data.modes_for(Some(&rule_name(&dd))));
+    dd_mode.set_width_request(220);
+    let e_pos = Entry::new();
+    e_pos.set_placeholder_text(Some("pos e.g. 0x0 or auto"));
+    e_pos.set_text("auto");
+    let e_scale = Entry::new();
+    e_scale.set_placeholder_text(Some("scale e.g. 1"));
+    e_scale.set_text("1");
+    let fields = RuleFields::monitor(&data.names);
+    row.append(&dd);
+    row.append(&dd_mode);
+    row.append(&e_pos);
+    row.append(&e_scale);
+    row.append(&fields.button);
+
+    let mut monitor_row = MonitorRow {
+        row: row.clone(),
+        origin,
+        saved: String::new(),
+        name: dd.clone(),
+        mode: dd_mode.clone(),
+        position: e_pos.clone(),
+        scale: e_scale.clone(),
+        fields,
+        tail: Vec::new(),
+    };
+    if let Some(value) = &value {
+        monitor_row.tail = monitor_row.show_rule(&data, value);
+    }
+    monitor_row.saved = monitor_row.rule();
+
+    // update modes on monitor change
+    let dd_mode_clone = dd_mode.clone();
+    let monitor_data = tables.monitor_data.clone();
+    dd.connect_selected_notify(move |sel_dd| {
+        let modes_list = monitor_data.borrow().modes_for(Some(&rule_name(sel_dd)));
+        let modes_refs: Vec<&str> = modes_list.iter().map(|s| s.as_str()).collect();
+        let new_model = gtk::StringList::new(&modes_refs);
+        dd_mode_clone.set_model(Some(&new_model));
+        dd_mode_clone.set_selected(0);
+    });
+
+    // Position and scale mean nothing to `disable` and `addreserved` rules
+    let special = |dd: &DropDown| is_special_mode(&selected_string(dd).unwrap_or_default());
+    e_pos.set_sensitive(!special(&dd_mode));
+    e_scale.set_sensitive(!special(&dd_mode));
+    monitor_row.fields.button.set_sensitive(!special(&dd_mode));
+    let (e_pos_clone, e_scale_clone) = (e_pos.clone(), e_scale.clone());
+    let button = monitor_row.fields.button.clone();
+    dd_mode.connect_selected_notify(move |dd| {
+        e_pos_clone.set_sensitive(!special(dd));
+        e_scale_clone.set_sensitive(!special(dd));
+        button.set_sensitive(!special(dd));
+    });
+
+    // Mark scales Hyprland would replace, and offer valid ones near the typed value
+    let check_scale = {
+        let (dd, dd_mode, e_scale) = (dd.clone(), dd_mode.clone(), e_scale.clone());
+        let monitor_data = tables.monitor_data.clone();
+        move || {
+            mark_scale(
+                &e_scale,
+                row_resolution(&dd, &dd_mode, &monitor_data.borrow()),
+            )
+        }
+    };
+    check_scale();
+    let (dd_clone, dd_mode_clone) = (dd.clone(), dd_mode.clone());
+    let monitor_data = tables.monitor_data.clone();
+    e_scale.connect_icon_press(move |entry, _| {
+        let resolution = row_resolution(&dd_clone, &dd_mode_clone, &monitor_data.borrow());
+        if let Some(resolution) = resolution {
+            show_scale_suggestions(entry, resolution);
+        }
+    });
+
+    let tables_clone = tables.clone();
+    let changed = changed_options.clone();
+    let list_clone = list.clone();
+    let notify = move || {
+        check_scale();
+        tables_clone.record_changes(&changed, &list_clone)
+    };
+    let n = notify.clone();
+    dd.connect_selected_notify(move |_| n());
+    let n = notify.clone();
+    dd_mode.connect_selected_notify(move |_| n());
+    let n = notify.clone();
+    e_pos.connect_changed(move |_| n());
+    let n = notify.clone();
+    e_scale.connect_changed(move |_| n());
+    monitor_row.fields.connect_changed(Rc::new(notify.clone()));
+
+    append_delete_button(list, &row, tables, changed_options);
+    list.append(&row);
+
+    let is_new = monitor_row.origin.is_none();
+    tables.monitors.borrow_mut().push(monitor_row);
+    if is_new {
+        notify();
+    }
+}
+
+pub fn add_workspace_row(
+    list: &ListBox,
+    rule: Option<(RuleOrigin, String)>,
+    tables: &RuleTables,
+    changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+) {
+    // workspace=WORKSPACE,monitor:NAME,default:true[,rule:value...]
+    let (origin, value) = match rule {
+        Some((origin, value)) => (Some(origin), value),
+        None => (None, String::new()),
+    };
+    let WorkspaceRule {
+        workspace,
+        monitor: mon_name,
+        default: is_default,
+        extra,
+    } = WorkspaceRule::parse(&value, origin.is_none());
+
+    let row = Box::new(Orientation::Horizontal, 6);
+    let e_workspace = Entry::new();
+    e_workspace.set_width_request(140);
+    e_workspace.set_placeholder_text(Some("1, name:web or r[1-5]"));
+    e_workspace.set_tooltip_text(Some(
+        "Workspace number, name:NAME, special:NAME, or a selector such as r[1-5]",
+    ));
+    e_workspace.set_text(&workspace);
+    row.append(&e_workspace);
+    // monitor dropdown
+    let mut names = tables.monitor_data.borrow().workspace_monitor_choices();
+    if let Some(n) = &mon_name
+        && !names.contains(n)
+    {
+        names.push(n.clone());
+    }
+    let mon_dd = string_dropdown(&names);
+    mon_dd.set_width_request(140);
+    if let Some(n) = &mon_name {
+        select_string(&mon_dd, n);
+    }
+    row.append(&mon_dd);
+    // default switch
+    let sw = Switch::new();
+    sw.set_halign(gtk::Align::Start);
+    sw.set_valign(gtk::Align::Center);
+    sw.set_active(is_default);
+    row.append(&sw);
+    let fields = RuleFields::workspace();
+    fields.load(&extra);
+    row.append(&fields.button);
+
+    let tables_clone = tables.clone();
+    let changed = changed_options.clone();
+    let list_clone = list.clone();
+    let notify = move || tables_clone.record_changes(&changed, &list_clone);
+    let n = notify.clone();
+    e_workspace.connect_changed(move |entry| {
+        set_error(entry, entry.text().trim().is_empty());
+        n();
+    });
+    let n = notify.clone();
+    mon_dd.connect_selected_notify(move |_| n());
+    let n = notify.clone();
+    sw.connect_active_notify(move |_| n());
+    fields.connect_changed(Rc::new(notify.clone()));
+
+    append_delete_button(list, &row, tables, changed_options);
+    list.append(&row);
+
+    let mut workspace_row = WorkspaceRow {
+        row,
+        origin,
+        saved: String::new(),
+        workspace: e_workspace,
+        monitor: mon_dd,
+        default: sw,
+        fields,
+    };
+    workspace_row.saved = workspace_row.rule();
+    let is_new = workspace_row.origin.is_none();
+    tables.workspaces.borrow_mut().push(workspace_row);
+    if is_new {
+        notify();
+    }
+}
+
+pub fn set_error(widget: &impl IsA<gtk::Widget>, error: bool) {
+    if error {
+        widget.add_css_class("error");
+    } else {
+        widget.remove_css_class("error");
+    }
+}
+
+fn append_delete_button(
+    list: &ListBox,
+    row: &Box,
+    tables: &RuleTables,
+    changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+) {
+    let del_btn = gtk::Button::from_icon_name("window-close-symbolic");
+    del_btn.set_has_frame(false);
+    del_btn.add_css_class("flat");
+    let list_ref = list.clone();
+    let row_ref = row.clone();
+    let tables = tables.clone();
+    let changed_options = changed_options.clone();
+    del_btn.connect_clicked(move |_| {
+        tables.remove_row(&list_ref, &row_ref);
+        tables.record_changes(&changed_options, &list_ref);
+    });
+    row.append(&del_btn);
+}
+
+/// Add `keyword=rule` after the last `keyword` line of the file `source`.
+fn add_rule(config: &mut HyprlandConfig, source: Option<usize>, keyword: &str, rule: &str) {
+    let source = source.filter(|idx| *idx < config.sourced_content.len());
+    if let Some(lines) = lines_mut(config, source) {
+        let insert_at = lines
+            .iter()
+            .rposition(|l| rule_value(l, keyword).is_some())
+            .map(|idx| idx + 1)
+            .unwrap_or(lines.len());
+        lines.insert(insert_at, format!("{}={}", keyword, rule));
+    }
+}
+
+/// The lines `origin` currently occupies in its file: the ones it was read from while they
+/// still hold it, or else the first lines holding it that aren't in `used`.
+fn locate(lines: &[String], origin: &RuleOrigin, used: &HashSet<usize>) -> Option<Range<usize>> {
+    let found: Vec<Range<usize>> = if origin.block {
+        monitorv2::find_blocks(lines, &origin.line)
+    } else {
+        (0..lines.len())
+            .filter(|idx| lines[*idx].trim() == origin.line)
+            .map(|idx| idx..idx + 1)
+            .collect()
+    };
+    let free = |range: &&Range<usize>| !used.contains(&range.start);
+    found
+        .iter()
+        .filter(free)
+        .find(|range| range.start == origin.index)
+        .or_else(|| found.iter().find(free))
+        .cloned()
+}
+
+/// Label of the catch-all rule in the name dropdown, whose name is empty in the file.
+const ANY_MONITOR: &str = "Any monitor";
+
+/// Label of the workspace monitor dropdown entry that leaves `monitor:` out.
+const NO_MONITOR: &str = "No monitor";
+
+/// The name for the rule, mapping the catch-all label back to an empty name.
+fn rule_name(dd: &DropDown) -> String {
+    match selected_string(dd) {
+        Some(name) if name == ANY_MONITOR => String::new(),
+        name => name.unwrap_or_default(),
+    }
+}
+
+/// The monitor name of a monitor rule, or the workspace of a workspace rule.
+pub fn first_field(rule: &str) -> &str {
+    rule.split(',').next().unwrap_or_default().trim()
+}
+
+fn shown_name(name: &str) -> &str {
+    if name.is_empty() { ANY_MONITOR } else { name }
+}
+
+/// The value of a `keyword = value` line with any trailing comment removed.
+pub fn rule_value(line: &str, keyword: &str) -> Option<String> {
+    let (key, value) = line.trim().split_once('=')?;
+    if key.trim() != keyword {
+        return None;
+    }
+    Some(value.split('#').next().unwrap_or(value).trim().to_string())
+}
+
+/// The resolution a monitor row runs at: its mode, or the output's current size.
+fn row_resolution(name: &DropDown, mode: &DropDown, data: &MonitorData) -> Option<(i32, i32)> {
+    parse_resolution(&selected_string(mode).unwrap_or_default())
+        .or_else(|| data.sizes.get(&rule_name(name)).copied())
+}
+
+/// Flag a scale Hyprland would reject for `resolution`; the entry icon opens valid ones.
+fn mark_scale(entry: &Entry, resolution: Option<(i32, i32)>) {
+    let text = entry.text();
+    let text = text.trim();
+    let problem = match (text.parse::<f64>(), resolution) {
+        _ if text == "auto" => None,
+        (Err(_), _) => Some("Not a number".to_string()),
+        (Ok(scale), Some(res)) if !scales::is_valid(scale, res) => Some(format!(
+            "Hyprland would replace this scale, since {}x{} divided by it isn't whole pixels. \
+             Click for valid scales nearby.",
+            res.0, res.1
+        )),
+        _ => None,
+    };
+    let secondary = gtk::EntryIconPosition::Secondary;
+    if let Some(problem) = problem {
+        entry.add_css_class("error");
+        entry.set_icon_from_icon_name(secondary, Some("dialog-warning-symbolic"));
+        entry.set_icon_tooltip_text(secondary, Some(&problem));
+    } else {
+        entry.remove_css_class("error");
+        let icon = resolution.map(|_| "pan-down-symbolic");
+        entry.set_icon_from_icon_name(secondary, icon);
+        entry.set_icon_tooltip_text(secondary, Some("Valid scales nearby"));
+    }
+}
+
+/// Pop up the valid scales around the typed one; picking one fills the entry.
+fn show_scale_suggestions(entry: &Entry, resolution: (i32, i32)) {
+    let typed = entry.text().trim().parse::<f64>().unwrap_or(1.0);
+    let popover = gtk::Popover::new();
+    let list = Box::new(Orientation::Vertical, 2);
+    for scale in scales::suggestions(typed, resolution, 4) {
+        let text = scales::format_scale(scale);
+        let label = format!(
+            "{}  ({}x{})",
+            text,
+            (resolution.0 as f64 / scale).round(),
+            (resolution.1 as f64 / scale).round()
+        );
+        let button = gtk::Button::with_label(&label);
+        button.add_css_class("flat");
+        let entry = entry.clone();
+        let popover_weak = popover.downgrade();
+        button.connect_clicked(move |_| {
+            entry.set_text(&text);
+            if let Some(popover) = popover_weak.upgrade() {
+                popover.popdown();
+            }
+        });
+        list.append(&button);
+    }
+    popover.set_child(Some(&list));
+    popover.set_parent(entry);
+    popover.connect_closed(|popover| {
+        let popover = popover.clone();
+        glib::idle_add_local_once(move || popover.unparent());
+    });
+    popover.popup();
+}
+
+/// `1920x1080@60` or `1920x1080` as width and height.
+fn parse_resolution(mode: &str) -> Option<(i32, i32)> {
+    let resolution = mode.split('@').next()?;
+    let (w, h) = resolution.split_once('x')?;
+    Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+}
+
+/// `1920x0` or `-1920x0` as coordinates; `None` for `auto` and its variants.
+fn parse_position(position: &str) -> Option<(i32, i32)> {
+    let (x, y) = position.split_once('x')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+pub fn string_dropdown(items: &[String]) -> DropDown {
+    let refs: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
+    let string_list = gtk::StringList::new(&refs);
+    DropDown::new(Some(string_list), None::<gtk::Expression>)
+}
+
+pub fn selected_string(dd: &DropDown) -> Option<String> {
+    dd.selected_item()
+        .and_then(|item| item.downcast::<gtk::StringObject>().ok())
+        .map(|obj| obj.string().to_string())
+}
+
+/// Replace the items of `dd`, keeping `selected` in the list and selected.
+pub fn set_strings(dd: &DropDown, mut items: Vec<String>, selected: Option<&str>) {
+    if let Some(value) = selected
+        && !items.iter().any(|i| i == value)
+    {
+        items.push(value.to_string());
+    }
+    let refs: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
+    dd.set_model(Some(&gtk::StringList::new(&refs)));
+    if let Some(value) = selected {
+        select_string(dd, value);
+    }
+}
+
+pub fn select_string(dd: &DropDown, value: &str) {
+    if let Some(model) = dd.model() {
+        for i in 0..model.n_items() {
+            if let Some(item) = model.item(i)
+                && let Some(obj) = item.downcast_ref::<gtk::StringObject>()
+                && obj.string() == value
+            {
+                dd.set_selected(i);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_commas_in_on_created_empty_commands() {
+        assert_eq!(
+            workspace_fields("special:term, gapsin:5, on-created-empty:kitty -e sh -c 'a, b'"),
+            [
+                "special:term",
+                "gapsin:5",
+                "on-created-empty:kitty -e sh -c 'a, b'"
+            ]
+        );
+        assert_eq!(
+            workspace_fields("1,monitor:DP-1,default:true"),
+            ["1", "monitor:DP-1", "default:true"]
+        );
+    }
+
+    #[test]
+    fn locates_identical_rules_by_their_line() {
+        let lines: Vec<String> = [
+            "workspace = 1, gapsin:0",
+            "# other",
+            "workspace = 1, gapsin:0",
+        ]
+        .map(str::to_string)
+        .to_vec();
+        let origin = |index| RuleOrigin {
+            source: None,
+            line: "workspace = 1, gapsin:0".to_string(),
+            block: false,
+            index,
+        };
+        assert_eq!(locate(&lines, &origin(2), &HashSet::new()), Some(2..3));
+        assert_eq!(locate(&lines, &origin(1), &HashSet::new()), Some(0..1));
+        assert_eq!(locate(&lines, &origin(0), &HashSet::from([0])), Some(2..3));
+    }
+}
@@ -0,0 +1,287 @@
+use std::ops::Range;
+
+use crate::config_lines::replace_value;
+
+/// Opening line of a block, e.g. `monitorv2 {`.
+fn is_block_start(line: &str) -> bool {
+    let line = line.trim();
+    line.strip_prefix("monitorv2")
+        .is_some_and(|rest| rest.trim_start().starts_with('{'))
+}
+
+/// Line ranges of the `monitorv2 { ... }` blocks in a file, closing brace included.
+fn block_ranges(lines: &[String]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (idx, line) in lines.iter().enumerate() {
+        if start.is_none() && is_block_start(line) {
+            start = Some(idx);
+        } else if let Some(begin) = start
+            && line.trim() == "}"
+        {
+            ranges.push(begin..idx + 1);
+            start = None;
+        }
+    }
+    ranges
+}
+
+/// The block's trimmed lines, which identify it the way a trimmed line identifies a rule.
+fn block_key(lines: &[String]) -> String {
+    lines
+        .iter()
+        .map(|l| l.trim())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Optional fields a `monitor=` rule takes as `key,value`, which blocks write as
+/// `key = value`. Other block keys have no `monitor=` form and stay out of the rule.
+const RULE_FIELDS: &[&str] = &[
+    "transform",
+    "mirror",
+    "bitdepth",
+    "cm",
+    "sdrbrightness",
+    "sdrsaturation",
+    "vrr",
+];
+
+/// The `key = value` lines of a block, by line index, with comments removed.
+fn block_entries(lines: &[String]) -> Vec<(usize, String, String)> {
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let line = line.split('#').next().unwrap_or(line).trim();
+            let (key, value) = line.split_once('=')?;
+            Some((idx, key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn is_true(value: &str) -> bool {
+    matches!(value, "true" | "yes" | "on" | "1")
+}
+
+/// Turn a block into the `NAME,MODE,POS,SCALE[,key,value...]` form of a `monitor=` rule,
+/// or `NAME,disable` / `NAME,addreserved,...` for blocks that do that.
+fn block_rule(lines: &[String]) -> String {
+    let entries = block_entries(lines);
+    let get = |wanted: &str| {
+        entries
+            .iter()
+            .find(|(_, key, _)| key == wanted)
+            .map(|(_, _, value)| value.as_str())
+    };
+    let output = get("output").unwrap_or_default();
+    if get("disabled").is_some_and(is_true) {
+        return format!("{},disable", output);
+    }
+    if let Some(reserved) = get("addreserved") {
+        let mut fields = vec![output, "addreserved"];
+        fields.extend(reserved.split(',').map(str::trim));
+        return fields.join(",");
+    }
+    let mut fields = vec![
+        output,
+        get("mode").unwrap_or("preferred"),
+        get("position").unwrap_or("auto"),
+        get("scale").unwrap_or("1"),
+    ];
+    for (_, key, value) in &entries {
+        if RULE_FIELDS.contains(&key.as_str()) {
+            fields.push(key);
+            fields.push(value);
+        }
+    }
+    fields.join(",")
+}
+
+/// The block keys and values a `monitor=` rule value stands for.
+fn rule_entries(rule: &str) -> Vec<(String, String)> {
+    let fields: Vec<&str> = rule.split(',').map(str::trim).collect();
+    let mut entries = vec![("output".to_string(), fields[0].to_string())];
+    let entry = |key: &str, value: &str| (key.to_string(), value.to_string());
+    match fields.get(1).copied().unwrap_or("preferred") {
+        "disable" | "disabled" => entries.push(entry("disabled", "true")),
+        "addreserved" => entries.push(entry("addreserved", &fields[2..].join(", "))),
+        mode => {
+            entries.push(entry("mode", mode));
+            entries.push(entry("position", fields.get(2).copied().unwrap_or("auto")));
+            entries.push(entry("scale", fields.get(3).copied().unwrap_or("1")));
+            // Fields pair up by their key, so a stray token can't shift the ones after it
+            let mut rest = fields.get(4..).unwrap_or_default().iter().peekable();
+            while let Some(key) = rest.next() {
+                if RULE_FIELDS.contains(key)
+                    && let Some(value) = rest.next_if(|value| !RULE_FIELDS.contains(*value))
+                {
+                    entries.push(entry(key, value));
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// Every block of a file as its first line index, identifying key and equivalent rule value.
+pub fn parse_blocks(lines: &[String]) -> Vec<(usize, String, String)> {
+    block_ranges(lines)
+        .into_iter()
+        .map(|range| {
+            let block = &lines[range.clone()];
+            (range.start, block_key(block), block_rule(block))
+        })
+        .collect()
+}
+
+/// The rule value of a block given by its key.
+pub fn block_value(key: &str) -> String {
+    let lines: Vec<String> = key.lines().map(str::to_string).collect();
+    block_rule(&lines)
+}
+
+/// Where the blocks identified by `key` currently are.
+pub fn find_blocks(lines: &[String], key: &str) -> Vec<Range<usize>> {
+    block_ranges(lines)
+        .into_iter()
+        .filter(|range| block_key(&lines[range.clone()]) == key)
+        .collect()
+}
+
+/// The block in `lines` with only the keys that differ between its current rule value
+/// and `rule` rewritten, added or removed. Comments, blank lines and keys a `monitor=`
+/// rule can't express are kept as they are.
+pub fn update_block(lines: &[String], rule: &str) -> Vec<String> {
+    let before = rule_entries(&block_rule(lines));
+    let after = rule_entries(rule);
+    let disabled = after.iter().any(|(key, _)| key == "disabled");
+    let entries = block_entries(lines);
+    let line_of = |wanted: &str| {
+        entries
+            .iter()
+            .find(|(_, key, _)| key == wanted)
+            .map(|(idx, _, _)| *idx)
+    };
+
+    let mut updated: Vec<Option<String>> = lines.iter().cloned().map(Some).collect();
+    let mut added = Vec::new();
+    for (key, value) in &after {
+        if before.iter().any(|(k, v)| k == key && v == value) {
+            continue;
+        }
+        match line_of(key) {
+            Some(idx) => updated[idx] = Some(replace_value(&lines[idx], value)),
+            None => added.push(format!("{} = {}", key, value)),
+        }
+    }
+    // A disabled output keeps its settings for when it's enabled again
+    for (key, _) in &before {
+        if !after.iter().any(|(k, _)| k == key)
+            && !disabled
+            && let Some(idx) = line_of(key)
+        {
+            updated[idx] = None;
+        }
+    }
+
+    let mut block: Vec<String> = updated.into_iter().flatten().collect();
+    let close = block.len().saturating_sub(1);
+    let inner = lines
+        .get(1..lines.len().saturating_sub(1))
+        .unwrap_or_default();
+    let indent: String = match inner.iter().find(|l| !l.trim().is_empty()) {
+        Some(line) => line.chars().take_while(|c| c.is_whitespace()).collect(),
+        None => {
+            let outer: String = lines[0].chars().take_while(|c| c.is_whitespace()).collect();
+            format!("{}    ", outer)
+        }
+    };
+    block.splice(
+        close..close,
+        added.into_iter().map(|line| format!("{}{}", indent, line)),
+    );
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    const BLOCK: &str = "monitorv2 {
+    # The desk monitor
+    output = DP-1
+    mode = 2560x1440@144
+    position = 0x0
+
+    scale = 1
+    bitdepth = 10 # for HDR
+    supports_hdr = 1
+}";
+
+    #[test]
+    fn keeps_block_only_keys_out_of_the_rule() {
+        assert_eq!(
+            block_rule(&lines(BLOCK)),
+            "DP-1,2560x1440@144,0x0,1,bitdepth,10"
+        );
+        assert_eq!(
+            block_rule(&lines(
+                "monitorv2 {\n  output = HDMI-A-1\n  disabled = true\n}"
+            )),
+            "HDMI-A-1,disable"
+        );
+        assert_eq!(
+            block_rule(&lines("monitorv2 {\n  output = desc:Dell Inc.\n}")),
+            "desc:Dell Inc.,preferred,auto,1"
+        );
+    }
+
+    #[test]
+    fn updates_only_changed_keys() {
+        let block = lines(BLOCK);
+        let updated = update_block(&block, "DP-1,2560x1440@144,1920x0,1.25,bitdepth,10,vrr,1");
+        let mut expected = block.clone();
+        expected[4] = "    position = 1920x0".to_string();
+        expected[6] = "    scale = 1.25".to_string();
+        expected.insert(9, "    vrr = 1".to_string());
+        assert_eq!(updated, expected);
+
+        let updated = update_block(&block, "DP-1,2560x1440@144,0x0,1");
+        let mut expected = block.clone();
+        expected.remove(7);
+        assert_eq!(updated, expected);
+    }
+
+    #[test]
+    fn disables_with_the_disabled_key() {
+        let block = lines(BLOCK);
+        let updated = update_block(&block, "DP-1,disable");
+        let mut expected = block.clone();
+        expected.insert(9, "    disabled = true".to_string());
+        assert_eq!(updated, expected);
+        assert_eq!(
+            update_block(&updated, "DP-1,2560x1440@144,0x0,1,bitdepth,10"),
+            block
+        );
+    }
+
+    #[test]
+    fn pairs_rule_fields_by_key() {
+        assert_eq!(
+            rule_entries("DP-1,preferred,auto,1,stray,transform,1,vrr"),
+            [
+                ("output", "DP-1"),
+                ("mode", "preferred"),
+                ("position", "auto"),
+                ("scale", "1"),
+                ("transform", "1"),
+            ]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+    }
+}
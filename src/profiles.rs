@@ -0,0 +1,230 @@
+use hyprparser::HyprlandConfig;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+use crate::ipc::Monitor;
+use crate::monitors::{self, MONITOR_KEYWORD, WORKSPACE_KEYWORD, first_field};
+use crate::monitorv2;
+use crate::settings::xdg_dir;
+
+/// File next to the main config that the active profile is written to.
+const PROFILE_FILE: &str = "hyprgui-monitors.conf";
+/// First line of the profile file, followed by the name of the active profile.
+const HEADER: &str = "# HyprGUI monitor profile: ";
+
+/// A named set of monitor rules and workspace assignments, e.g. "docked" or "laptop".
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    /// `monitor=` rule values.
+    pub monitors: Vec<String>,
+    /// `workspace=` rule values.
+    pub workspaces: Vec<String>,
+}
+
+impl Profile {
+    /// Connector names and `desc:` identifiers the profile has rules for.
+    fn outputs(&self) -> Vec<&str> {
+        self.monitors
+            .iter()
+            .filter_map(|rule| rule.split(',').next())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    /// Whether the profile has a rule for every connected output and no others.
+    pub fn matches(&self, connected: &[Monitor]) -> bool {
+        let outputs = self.outputs();
+        !connected.is_empty()
+            && outputs
+                .iter()
+                .all(|output| connected.iter().any(|m| identifies(output, m)))
+            && connected
+                .iter()
+                .all(|m| outputs.iter().any(|output| identifies(output, m)))
+    }
+
+    /// The content of the profile file with this profile active.
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            format!("{}{}", HEADER, self.name),
+            "# Written by HyprGUI when switching profiles on the Monitors page.".to_string(),
+            String::new(),
+        ];
+        lines.extend(
+            self.monitors
+                .iter()
+                .map(|rule| format!("{} = {}", MONITOR_KEYWORD, rule)),
+        );
+        if !self.workspaces.is_empty() {
+            lines.push(String::new());
+            lines.extend(
+                self.workspaces
+                    .iter()
+                    .map(|rule| format!("{} = {}", WORKSPACE_KEYWORD, rule)),
+            );
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+/// Whether a rule's name selects the output, as Hyprland matches `desc:` by prefix.
+fn identifies(output: &str, monitor: &Monitor) -> bool {
+    match output.strip_prefix("desc:") {
+        Some(desc) => monitors::description(monitor).is_some_and(|d| d.starts_with(desc.trim())),
+        None => output == monitor.name,
+    }
+}
+
+/// Monitor profiles, stored in `$XDG_CONFIG_HOME/hyprgui/profiles.json`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Profiles {
+    pub profiles: Vec<Profile>,
+}
+
+impl Profiles {
+    pub fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    fn path() -> PathBuf {
+        xdg_dir("XDG_CONFIG_HOME", ".config")
+            .join("hyprgui")
+            .join("profiles.json")
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.iter().map(|p| p.name.clone()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Store `profile`, replacing the one with the same name.
+    pub fn insert(&mut self, profile: Profile) {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.profiles.retain(|p| p.name != name);
+    }
+
+    /// The first profile made for exactly the connected outputs.
+    pub fn suggest(&self, connected: &[Monitor]) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.matches(connected))
+    }
+}
+
+/// Where the active profile of the config at `config_path` is written.
+pub fn profile_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(PROFILE_FILE)
+}
+
+/// Name of the profile currently written to `path`, if any.
+pub fn active_profile(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let name = content.lines().next()?.strip_prefix(HEADER)?.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// The monitor and workspace rules in a profile file, including edits made to it since
+/// it was written.
+pub fn read_rules(content: &str) -> (Vec<String>, Vec<String>) {
+    let lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let mut found: Vec<(usize, String)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, line)| monitors::rule_value(line, MONITOR_KEYWORD).map(|v| (idx, v)))
+        .collect();
+    found.extend(
+        monitorv2::parse_blocks(&lines)
+            .into_iter()
+            .map(|(start, _, rule)| (start, rule)),
+    );
+    found.sort_by_key(|(idx, _)| *idx);
+    let workspaces = lines
+        .iter()
+        .filter_map(|line| monitors::rule_value(line, WORKSPACE_KEYWORD))
+        .collect();
+    (
+        found.into_iter().map(|(_, rule)| rule).collect(),
+        workspaces,
+    )
+}
+
+/// Index of the profile file among the sourced files.
+pub fn source_index(config: &HyprlandConfig, path: &Path) -> Option<usize> {
+    let wanted = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    config.sourced_paths.iter().position(|sourced| {
+        let sourced = Path::new(sourced);
+        fs::canonicalize(sourced).unwrap_or_else(|_| sourced.to_path_buf()) == wanted
+    })
+}
+
+/// Whether the main config has a `source =` line for `path`, even if the file is missing.
+pub fn is_sourced(config: &HyprlandConfig, path: &Path) -> bool {
+    if source_index(config, path).is_some() {
+        return true;
+    }
+    let home = env::var("HOME").unwrap_or_default();
+    config.content.iter().any(|line| {
+        monitors::rule_value(line, "source")
+            .is_some_and(|value| Path::new(&value.replacen('~', &home, 1)) == path)
+    })
+}
+
+/// The line that makes the main config read the profile file.
+pub fn source_line(path: &Path) -> String {
+    format!("source = {}", path.display())
+}
+
+/// Drop the monitor rules for the outputs `profile` has rules for, and the workspace
+/// rules for the workspaces it assigns, so the profile's own rules take over.
+pub fn remove_rules(lines: &mut Vec<String>, profile: &Profile) {
+    let outputs: Vec<&str> = profile
+        .monitors
+        .iter()
+        .map(|rule| first_field(rule))
+        .collect();
+    let workspaces: Vec<&str> = profile
+        .workspaces
+        .iter()
+        .map(|rule| first_field(rule))
+        .collect();
+
+    let blocks: Vec<(usize, String, String)> = monitorv2::parse_blocks(lines);
+    for (start, key, rule) in blocks.into_iter().rev() {
+        if outputs.contains(&first_field(&rule)) {
+            lines.drain(start..start + key.lines().count());
+        }
+    }
+
+    lines.retain(|line| {
+        let moved = |keyword: &str, names: &[&str]| {
+            monitors::rule_value(line, keyword)
+                .is_some_and(|value| names.contains(&first_field(&value)))
+        };
+        !moved(MONITOR_KEYWORD, &outputs) && !moved(WORKSPACE_KEYWORD, &workspaces)
+    });
+}
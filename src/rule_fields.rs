@@ -0,0 +1,387 @@
+use gtk::{DropDown, Entry, Grid, Label, MenuButton, Popover, prelude::*};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A typed widget for one `key,value` or `key:value` field of a rule.
+enum FieldWidget {
+    /// Dropdown over fixed values; the empty value leaves the field out.
+    Choice(DropDown, RefCell<Vec<String>>),
+    Text(Entry),
+}
+
+impl FieldWidget {
+    fn choice(options: &[(&str, &str)]) -> Self {
+        let labels: Vec<&str> = options.iter().map(|(_, label)| *label).collect();
+        let values = options.iter().map(|(value, _)| value.to_string()).collect();
+        let dd = DropDown::new(Some(gtk::StringList::new(&labels)), None::<gtk::Expression>);
+        FieldWidget::Choice(dd, RefCell::new(values))
+    }
+
+    fn value(&self) -> String {
+        match self {
+            FieldWidget::Choice(dd, values) => values
+                .borrow()
+                .get(dd.selected() as usize)
+                .cloned()
+                .unwrap_or_default(),
+            FieldWidget::Text(entry) => entry.text().trim().to_string(),
+        }
+    }
+
+    /// Select `value`, adding it to a dropdown that doesn't offer it yet.
+    fn set_value(&self, value: &str) {
+        match self {
+            FieldWidget::Choice(dd, values) => {
+                let position = values.borrow().iter().position(|v| v == value);
+                let position = position.unwrap_or_else(|| {
+                    values.borrow_mut().push(value.to_string());
+                    if let Some(list) = dd.model().and_downcast::<gtk::StringList>() {
+                        list.append(value);
+                    }
+                    values.borrow().len() - 1
+                });
+                dd.set_selected(position as u32);
+            }
+            FieldWidget::Text(entry) => entry.set_text(value),
+        }
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        match self {
+            FieldWidget::Choice(dd, _) => dd.clone().upcast(),
+            FieldWidget::Text(entry) => entry.clone().upcast(),
+        }
+    }
+}
+
+/// Where an extra field sits in the rule, so rewriting a row keeps the original order.
+#[derive(Clone)]
+enum Token {
+    Known(&'static str),
+    /// A field this editor doesn't know, kept exactly as written.
+    Verbatim(String),
+}
+
+const TRANSFORMS: &[(&str, &str)] = &[
+    ("", "Default"),
+    ("0", "Normal"),
+    ("1", "90°"),
+    ("2", "180°"),
+    ("3", "270°"),
+    ("4", "Flipped"),
+    ("5", "Flipped, 90°"),
+    ("6", "Flipped, 180°"),
+    ("7", "Flipped, 270°"),
+];
+
+const BITDEPTHS: &[(&str, &str)] = &[("", "Default"), ("8", "8 bit"), ("10", "10 bit")];
+
+const VRR_MODES: &[(&str, &str)] = &[
+    ("", "Default"),
+    ("0", "Off"),
+    ("1", "On"),
+    ("2", "Fullscreen only"),
+    ("3", "Fullscreen video or game"),
+];
+
+const COLOR_MODES: &[(&str, &str)] = &[
+    ("", "Default"),
+    ("auto", "Auto"),
+    ("srgb", "sRGB"),
+    ("dcip3", "DCI P3"),
+    ("dp3", "Display P3"),
+    ("adobe", "Adobe RGB"),
+    ("wide", "Wide (BT2020)"),
+    ("edid", "EDID primaries"),
+    ("hdr", "HDR"),
+    ("hdredid", "HDR with EDID primaries"),
+];
+
+const BOOLS: &[(&str, &str)] = &[("", "Default"), ("true", "Yes"), ("false", "No")];
+
+/// Split optional fields into their order and the values of the `known` keys. `,` fields
+/// come in `key,value` pairs, `:` fields as `key:value` items.
+fn split_tokens(
+    extra: &[String],
+    separator: char,
+    known: &[&'static str],
+) -> (Vec<Token>, Vec<(&'static str, String)>) {
+    let items: Vec<(String, Option<String>)> = if separator == ',' {
+        extra
+            .chunks(2)
+            .map(|pair| (pair[0].clone(), pair.get(1).cloned()))
+            .collect()
+    } else {
+        extra
+            .iter()
+            .map(|item| match item.split_once(separator) {
+                Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                None => (item.clone(), None),
+            })
+            .collect()
+    };
+    let mut order = Vec::new();
+    let mut values = Vec::new();
+    for (key, value) in items {
+        match (known.iter().find(|k| **k == key), value) {
+            (Some(key), Some(value)) => {
+                order.push(Token::Known(key));
+                values.push((*key, value));
+            }
+            (_, Some(value)) => {
+                order.push(Token::Verbatim(format!("{}{}{}", key, separator, value)))
+            }
+            (_, None) => order.push(Token::Verbatim(key)),
+        }
+    }
+    (order, values)
+}
+
+/// Write fields back in `order`, taking known values from `value` and adding known keys
+/// missing from `order` last. Empty values leave their field out.
+fn join_tokens(
+    order: &[Token],
+    separator: char,
+    known: &[&'static str],
+    value: impl Fn(&str) -> String,
+) -> Vec<String> {
+    let pair = |key: &str| {
+        Some(value(key))
+            .filter(|value| !value.is_empty())
+            .map(|value| format!("{}{}{}", key, separator, value))
+    };
+    let mut out = Vec::new();
+    for token in order {
+        match token {
+            Token::Known(key) => out.extend(pair(key)),
+            Token::Verbatim(raw) => out.push(raw.clone()),
+        }
+    }
+    for key in known {
+        if !order
+            .iter()
+            .any(|t| matches!(t, Token::Known(k) if k == key))
+        {
+            out.extend(pair(key));
+        }
+    }
+    out
+}
+
+fn text(placeholder: &str) -> FieldWidget {
+    let entry = Entry::new();
+    entry.set_placeholder_text(Some(placeholder));
+    FieldWidget::Text(entry)
+}
+
+/// Editor for the optional fields of a rule: typed widgets for the common ones, and every
+/// other field carried along verbatim.
+pub struct RuleFields {
+    fields: Vec<(&'static str, &'static str, FieldWidget)>,
+    /// `,` for `monitor=` fields (`transform,1`), `:` for `workspace=` ones (`gapsin:5`).
+    separator: char,
+    order: RefCell<Vec<Token>>,
+    pub button: MenuButton,
+}
+
+impl RuleFields {
+    /// The fields after `NAME,MODE,POS,SCALE` of a `monitor=` rule.
+    pub fn monitor(monitor_names: &[String]) -> Self {
+        let mut mirrors: Vec<(&str, &str)> = vec![("", "None")];
+        mirrors.extend(monitor_names.iter().map(|n| (n.as_str(), n.as_str())));
+        let fields = vec![
+            ("transform", "Transform", FieldWidget::choice(TRANSFORMS)),
+            ("mirror", "Mirror of", FieldWidget::choice(&mirrors)),
+            ("bitdepth", "Bit depth", FieldWidget::choice(BITDEPTHS)),
+            ("vrr", "Adaptive sync", FieldWidget::choice(VRR_MODES)),
+            ("cm", "Color mode", FieldWidget::choice(COLOR_MODES)),
+            ("sdrbrightness", "SDR brightness", text("e.g. 1.2")),
+            ("sdrsaturation", "SDR saturation", text("e.g. 1.0")),
+        ];
+        Self::new(
+            fields,
+            ',',
+            "Transform, mirroring, color and other settings",
+        )
+    }
+
+    /// The rules after the workspace of a `workspace=` rule, apart from `monitor` and
+    /// `default`, which the row shows itself.
+    pub fn workspace() -> Self {
+        let fields = vec![
+            ("gapsin", "Inner gaps", text("e.g. 5")),
+            ("gapsout", "Outer gaps", text("e.g. 10")),
+            ("bordersize", "Border size", text("e.g. 2")),
+            ("border", "Borders", FieldWidget::choice(BOOLS)),
+            ("shadow", "Shadows", FieldWidget::choice(BOOLS)),
+            ("rounding", "Rounding", FieldWidget::choice(BOOLS)),
+            ("decorate", "Decorations", FieldWidget::choice(BOOLS)),
+            ("persistent", "Persistent", FieldWidget::choice(BOOLS)),
+            (
+                "on-created-empty",
+                "On created empty",
+                text("command to run"),
+            ),
+            ("defaultName", "Default name", text("e.g. web")),
+            ("layoutopt", "Layout option", text("e.g. orientation:left")),
+        ];
+        Self::new(fields, ':', "Gaps, borders, persistence and other rules")
+    }
+
+    fn new(
+        fields: Vec<(&'static str, &'static str, FieldWidget)>,
+        separator: char,
+        tooltip: &str,
+    ) -> Self {
+        let grid = Grid::new();
+        grid.set_row_spacing(6);
+        grid.set_column_spacing(12);
+        grid.set_margin_top(6);
+        grid.set_margin_bottom(6);
+        grid.set_margin_start(6);
+        grid.set_margin_end(6);
+        for (idx, (_, label, widget)) in fields.iter().enumerate() {
+            let label = Label::new(Some(label));
+            label.set_halign(gtk::Align::Start);
+            grid.attach(&label, 0, idx as i32, 1, 1);
+            grid.attach(&widget.widget(), 1, idx as i32, 1, 1);
+        }
+        let popover = Popover::new();
+        popover.set_child(Some(&grid));
+
+        let button = MenuButton::new();
+        button.set_icon_name("view-more-symbolic");
+        button.set_tooltip_text(Some(tooltip));
+        button.set_popover(Some(&popover));
+
+        RuleFields {
+            fields,
+            separator,
+            order: RefCell::new(Vec::new()),
+            button,
+        }
+    }
+
+    /// Take the optional fields as split at commas. Unknown keys, and a dangling key
+    /// without value, are kept verbatim.
+    pub fn load(&self, extra: &[String]) {
+        let (order, values) = split_tokens(extra, self.separator, &self.keys());
+        *self.order.borrow_mut() = order;
+        for (_, _, widget) in &self.fields {
+            widget.set_value("");
+        }
+        for (key, value) in values {
+            if let Some(field) = self.field(key) {
+                field.set_value(&value);
+            }
+        }
+    }
+
+    /// The fields as written in the rule, in their original order with new ones last.
+    pub fn render(&self) -> Vec<String> {
+        join_tokens(&self.order.borrow(), self.separator, &self.keys(), |key| {
+            self.field(key).map(|f| f.value()).unwrap_or_default()
+        })
+    }
+
+    fn keys(&self) -> Vec<&'static str> {
+        self.fields.iter().map(|(key, _, _)| *key).collect()
+    }
+
+    pub fn transform(&self) -> u32 {
+        self.field("transform")
+            .and_then(|f| f.value().parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Offer `names` as mirror sources, keeping the current one.
+    pub fn set_monitor_names(&self, names: &[String]) {
+        let Some(mirror @ FieldWidget::Choice(dd, values)) = self.field("mirror") else {
+            return;
+        };
+        let current = mirror.value();
+        let mut new_values = vec![String::new()];
+        new_values.extend(names.iter().cloned());
+        if !new_values.contains(&current) {
+            new_values.push(current.clone());
+        }
+        let labels: Vec<&str> = new_values
+            .iter()
+            .map(|v| if v.is_empty() { "None" } else { v.as_str() })
+            .collect();
+        dd.set_model(Some(&gtk::StringList::new(&labels)));
+        *values.borrow_mut() = new_values;
+        mirror.set_value(&current);
+    }
+
+    /// Call `f` whenever any of the fields changes.
+    pub fn connect_changed(&self, f: Rc<dyn Fn()>) {
+        for (_, _, widget) in &self.fields {
+            let f = f.clone();
+            match widget {
+                FieldWidget::Choice(dd, _) => {
+                    dd.connect_selected_notify(move |_| f());
+                }
+                FieldWidget::Text(entry) => {
+                    entry.connect_changed(move |_| f());
+                }
+            }
+        }
+    }
+
+    fn field(&self, key: &str) -> Option<&FieldWidget> {
+        self.fields
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, _, widget)| widget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn round_trip(extra: &[&str], separator: char, known: &[&'static str]) -> Vec<String> {
+        let (order, values) = split_tokens(&strings(extra), separator, known);
+        join_tokens(&order, separator, known, |key| {
+            values
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default()
+        })
+    }
+
+    #[test]
+    fn keeps_monitor_fields_in_order() {
+        let extra = ["vrr", "1", "foo", "bar", "transform", "2", "dangling"];
+        assert_eq!(
+            round_trip(&extra, ',', &["transform", "vrr"]),
+            strings(&["vrr,1", "foo,bar", "transform,2", "dangling"])
+        );
+    }
+
+    #[test]
+    fn keeps_workspace_fields_in_order() {
+        let extra = ["gapsin:5", "default:true", "persistent"];
+        assert_eq!(
+            round_trip(&extra, ':', &["default", "gapsin"]),
+            strings(&["gapsin:5", "default:true", "persistent"])
+        );
+    }
+
+    #[test]
+    fn adds_new_fields_last_and_drops_empty_ones() {
+        let (order, _) = split_tokens(&strings(&["gapsin:5"]), ':', &["gapsin", "rounding"]);
+        let rendered = join_tokens(&order, ':', &["gapsin", "rounding"], |key| match key {
+            "rounding" => "false".to_string(),
+            _ => String::new(),
+        });
+        assert_eq!(rendered, strings(&["rounding:false"]));
+    }
+}
@@ -0,0 +1,85 @@
+use hyprparser::HyprlandConfig;
+use std::collections::HashMap;
+
+use std::sync::Arc;
+
+use crate::ipc::{self, HyprlandRequests, SocketClient};
+
+/// Current values of options in the running compositor, keyed by `section:key`. Options
+/// it doesn't know are left out; the map is empty without a `client`.
+pub async fn query_options(
+    client: Option<Arc<SocketClient>>,
+    keys: Vec<String>,
+) -> HashMap<String, String> {
+    let Some(client) = client else {
+        return HashMap::new();
+    };
+    match ipc::call(client, move |c| c.getoptions(&keys)).await {
+        Ok(options) => options
+            .iter()
+            .filter_map(|option| Some((option.option.clone(), option.to_config_string()?)))
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to query option values: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Whether a file value and a runtime value mean the same thing, e.g. `yes` and `1`,
+/// `0.50` and `0.5`, or `5` and the `5 5 5 5` Hyprland reports for gaps.
+pub fn same_value(file: &str, runtime: &str) -> bool {
+    let (file, runtime) = (normalize(file), normalize(runtime));
+    if file == runtime {
+        return true;
+    }
+    if let (Ok(a), Ok(b)) = (file.parse::<f64>(), runtime.parse::<f64>()) {
+        return (a - b).abs() < 1e-4;
+    }
+    let parser = HyprlandConfig::new();
+    match (parser.parse_color(&file), parser.parse_color(&runtime)) {
+        (Some(a), Some(b)) => {
+            let close = |x: f32, y: f32| (x - y).abs() < 0.01;
+            close(a.0, b.0) && close(a.1, b.1) && close(a.2, b.2) && close(a.3, b.3)
+        }
+        _ => false,
+    }
+}
+
+fn normalize(value: &str) -> String {
+    let value = value.trim();
+    match value {
+        "true" | "yes" | "on" => return "1".to_string(),
+        "false" | "no" | "off" => return "0".to_string(),
+        _ => {}
+    }
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if parts.len() > 1 && parts.iter().all(|p| *p == parts[0]) {
+        return parts[0].to_string();
+    }
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_values_written_differently() {
+        assert!(same_value("yes", "1"));
+        assert!(same_value("off", "false"));
+        assert!(same_value("0.50", "0.5"));
+        assert!(same_value("5", "5 5 5 5"));
+        assert!(same_value("rgba(33ccffee)", "rgba(33ccffee)"));
+        assert!(same_value("rgb(ff0000)", "0xffff0000"));
+    }
+
+    #[test]
+    fn tells_different_values_apart() {
+        assert!(!same_value("1", "0"));
+        assert!(!same_value("5", "5 10 5 10"));
+        assert!(!same_value("0.5", "0.6"));
+        assert!(!same_value("rgb(ff0000)", "rgb(00ff00)"));
+        assert!(!same_value("master", "dwindle"));
+    }
+}
@@ -0,0 +1,82 @@
+/// Fractional scales are sent to clients in 120ths (wp_fractional_scale_v1).
+const STEPS: f64 = 120.0;
+/// How far from a whole logical pixel Hyprland still accepts.
+const TOLERANCE: f64 = 0.01;
+/// How many 120ths Hyprland searches in each direction for a replacement scale.
+const SEARCH_STEPS: i32 = 90;
+
+/// Whether `scale` divides the resolution into whole logical pixels, as Hyprland requires.
+pub fn is_valid(scale: f64, (width, height): (i32, i32)) -> bool {
+    if scale <= 0.0 {
+        return false;
+    }
+    let whole = |pixels: i32| {
+        let logical = pixels as f64 / scale;
+        (logical - logical.round()).abs() < TOLERANCE
+    };
+    whole(width) && whole(height)
+}
+
+/// Valid scales closest to `scale`, searched in 120ths up and down from it like Hyprland
+/// does when it replaces an invalid one. At most `count` per direction, smallest first.
+pub fn suggestions(scale: f64, resolution: (i32, i32), count: usize) -> Vec<f64> {
+    let base = (scale * STEPS).round() as i32;
+    let valid = |step: i32| {
+        let value = step as f64 / STEPS;
+        (step > 0 && is_valid(value, resolution)).then_some(value)
+    };
+    let mut found: Vec<f64> = (0..SEARCH_STEPS)
+        .filter_map(|i| valid(base - i))
+        .take(count)
+        .collect();
+    found.reverse();
+    found.extend(
+        (1..SEARCH_STEPS)
+            .filter_map(|i| valid(base + i))
+            .take(count),
+    );
+    found
+}
+
+/// A scale as it should appear in the config, without trailing zeros.
+pub fn format_scale(scale: f64) -> String {
+    let text = format!("{:.6}", scale);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_scales_that_give_whole_pixels() {
+        assert!(is_valid(1.0, (2560, 1440)));
+        assert!(is_valid(1.25, (2560, 1440)));
+        assert!(is_valid(1.333333, (2560, 1440)));
+        assert!(is_valid(2.0, (3840, 2160)));
+        assert!(is_valid(1.5, (1920, 1080)));
+    }
+
+    #[test]
+    fn rejects_scales_that_give_fractional_pixels() {
+        assert!(!is_valid(1.15, (2560, 1440)));
+        assert!(!is_valid(1.3, (1920, 1080)));
+        assert!(!is_valid(0.0, (1920, 1080)));
+    }
+
+    #[test]
+    fn suggests_the_nearest_valid_scales() {
+        let found = suggestions(1.15, (2560, 1440), 1);
+        assert_eq!(found.len(), 2);
+        assert!((found[0] - 128.0 / 120.0).abs() < 1e-9);
+        assert!((found[1] - 1.25).abs() < 1e-9);
+        assert!(found.iter().all(|s| is_valid(*s, (2560, 1440))));
+    }
+
+    #[test]
+    fn formats_without_trailing_zeros() {
+        assert_eq!(format_scale(1.25), "1.25");
+        assert_eq!(format_scale(2.0), "2");
+        assert_eq!(format_scale(4.0 / 3.0), "1.333333");
+    }
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+use crate::drm;
+
+/// Preferences of HyprGUI itself, stored in `$XDG_CONFIG_HOME/hyprgui/settings.json`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// Number of backup sets kept before the oldest ones are deleted.
+    pub backup_limit: usize,
+    /// Whether edits are pushed to the running compositor as they are made.
+    pub live_preview: bool,
+    /// Whether Hyprland is asked to reload after saving, to report config errors.
+    pub reload_after_save: bool,
+    /// DRM sysfs directory read for connected monitors when no compositor answers.
+    pub drm_root: PathBuf,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            backup_limit: 10,
+            live_preview: false,
+            reload_after_save: true,
+            drm_root: PathBuf::from(drm::DEFAULT_ROOT),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    fn path() -> PathBuf {
+        xdg_dir("XDG_CONFIG_HOME", ".config")
+            .join("hyprgui")
+            .join("settings.json")
+    }
+}
+
+/// Resolve an XDG base directory, falling back to `$HOME/<fallback>`.
+pub fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    match env::var(var) {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string())).join(fallback),
+    }
+}
@@ -7,7 +7,16 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
+use crate::config_lines;
+use crate::defaults::Defaults;
 use crate::gui::get_option_limits;
+use crate::history::EditHistory;
+
+/// Pending value in `changed_options` for an option that should be removed from the config.
+pub const UNSET: &str = "\0unset";
+
+/// Window action that refreshes the modified-state indicators.
+pub const CHANGES_ACTION: &str = "win.changes-updated";
 
 pub struct WidgetBuilder {
     pub options: HashMap<String, Widget>,
@@ -330,58 +339,52 @@ impl WidgetBuilder {
         config: &HyprlandConfig,
         category: &str,
         changed_options: Rc<RefCell<HashMap<(String, String), String>>>,
+        history: Rc<RefCell<EditHistory>>,
+        defaults: &Defaults,
     ) {
         for (name, widget) in &self.options {
+            let default = defaults.get(&config_lines::full_key(category, name));
+            if let Some(row) = widget.parent().and_downcast::<Box>() {
+                Self::add_reset_button(
+                    &row,
+                    widget,
+                    (category.to_string(), name.to_string()),
+                    default.map(|d| d.to_string()),
+                    changed_options.clone(),
+                    history.clone(),
+                );
+            }
+
             let value = self.extract_value(config, category, name);
+            let key = (category.to_string(), name.to_string());
+            let changed_options = changed_options.clone();
+            let history_clone = history.clone();
             if let Some(spin_button) = widget.downcast_ref::<gtk::SpinButton>() {
                 let float_value = value.parse::<f64>().unwrap_or(0.0);
                 spin_button.set_value(float_value);
-                let category = category.to_string();
-                let name = name.to_string();
-                let changed_options = changed_options.clone();
                 spin_button.connect_value_changed(move |sb| {
-                    let mut changes = changed_options.borrow_mut();
                     let new_value = sb.value().to_string();
-                    changes.insert((category.clone(), name.clone()), new_value);
+                    record_change(sb, &changed_options, &history_clone, &key, new_value);
                 });
             } else if let Some(entry) = widget.downcast_ref::<Entry>() {
                 entry.set_text(&value);
-                let category = category.to_string();
-                let name = name.to_string();
-                let changed_options = changed_options.clone();
                 entry.connect_changed(move |entry| {
-                    let mut changes = changed_options.borrow_mut();
                     let new_value = entry.text().to_string();
-                    changes.insert((category.clone(), name.clone()), new_value);
+                    record_change(entry, &changed_options, &history_clone, &key, new_value);
                 });
             } else if let Some(switch) = widget.downcast_ref::<Switch>() {
-                switch.set_active(value == "true");
-                let category = category.to_string();
-                let name = name.to_string();
-                let changed_options = changed_options.clone();
+                switch.set_active(is_true(&value));
                 switch.connect_active_notify(move |sw| {
-                    let mut changes = changed_options.borrow_mut();
                     let new_value = sw.is_active().to_string();
-                    changes.insert((category.clone(), name.clone()), new_value);
+                    record_change(sw, &changed_options, &history_clone, &key, new_value);
                 });
             } else if let Some(color_button) = widget.downcast_ref::<ColorButton>() {
                 if let Some((red, green, blue, alpha)) = config.parse_color(&value) {
                     color_button.set_rgba(&gdk::RGBA::new(red, green, blue, alpha));
                 }
-                let category = category.to_string();
-                let name = name.to_string();
-                let changed_options = changed_options.clone();
                 color_button.connect_color_set(move |cb| {
-                    let mut changes = changed_options.borrow_mut();
-                    let new_color = cb.rgba();
-                    let new_value = format!(
-                        "rgba({:02X}{:02X}{:02X}{:02X})",
-                        (new_color.red() * 255.0) as u8,
-                        (new_color.green() * 255.0) as u8,
-                        (new_color.blue() * 255.0) as u8,
-                        (new_color.alpha() * 255.0) as u8
-                    );
-                    changes.insert((category.clone(), name.clone()), new_value);
+                    let new_value = format_rgba(&cb.rgba());
+                    record_change(cb, &changed_options, &history_clone, &key, new_value);
                 });
             } else if let Some(dropdown) = widget.downcast_ref::<gtk::DropDown>() {
                 let model = dropdown.model().unwrap();
@@ -394,173 +397,185 @@ impl WidgetBuilder {
                         break;
                     }
                 }
-                let category = category.to_string();
-                let name = name.to_string();
-                let changed_options = changed_options.clone();
                 dropdown.connect_selected_notify(move |dd| {
-                    let mut changes = changed_options.borrow_mut();
                     if let Some(selected) = dd.selected_item()
                         && let Some(string_object) = selected.downcast_ref::<gtk::StringObject>()
                     {
                         let new_value = string_object.string().to_string();
-                        changes.insert((category.clone(), name.clone()), new_value);
+                        record_change(dd, &changed_options, &history_clone, &key, new_value);
                     }
                 });
             }
+            if let Some(initial) = widget_value(widget) {
+                history
+                    .borrow_mut()
+                    .set_initial((category.to_string(), name.to_string()), initial);
+            }
         }
     }
 
-    pub fn extract_value(&self, config: &HyprlandConfig, category: &str, name: &str) -> String {
-        let mut value = String::new();
-        let parts: Vec<&str> = name.split(':').collect();
-
-        // Special-case: the "layouts" UI page maps to top-level sections "dwindle" and "master"
-        // in hyprland.conf. Read those directly instead of a non-existent "layouts" section.
-        if category == "layouts" && parts.len() == 2 {
-            // Try main config first
-            if let Some(&(start, end)) = config.sections.get(parts[0])
-                && start < config.content.len()
-                && end < config.content.len()
-            {
-                for line in &config.content[start..=end] {
-                    let trimmed = line.trim();
-                    if trimmed.starts_with(parts[1])
-                        && trimmed[parts[1].len()..].trim_start().starts_with('=')
-                        && let Some(val) = line.split('=').nth(1)
-                    {
-                        value = val.trim().to_string();
-                        break;
-                    }
-                }
+    /// Show the Hyprland default at the end of an option row, with a button that removes
+    /// the option from the config so the default takes effect.
+    fn add_reset_button(
+        row: &Box,
+        widget: &Widget,
+        key: (String, String),
+        default: Option<String>,
+        changed_options: Rc<RefCell<HashMap<(String, String), String>>>,
+        history: Rc<RefCell<EditHistory>>,
+    ) {
+        let default_label = Label::new(Some(&format!(
+            "Default: {}",
+            match default.as_deref() {
+                Some("") => "empty",
+                Some(value) => value,
+                None => "unknown",
             }
-
-            // If not found, scan sourced files
-            if value.is_empty() {
-                for idx in 0..config.sourced_content.len() {
-                    let section_key = format!("{}_{}", parts[0], idx);
-                    if let Some(&(start, end)) = config.sourced_sections.get(&section_key) {
-                        let sourced = &config.sourced_content[idx];
-                        if start < sourced.len() && end < sourced.len() {
-                            for line in &sourced[start..=end] {
-                                let trimmed = line.trim();
-                                if trimmed.starts_with(parts[1])
-                                    && trimmed[parts[1].len()..].trim_start().starts_with('=')
-                                    && let Some(val) = line.split('=').nth(1)
-                                {
-                                    value = val.trim().to_string();
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    if !value.is_empty() {
-                        break;
-                    }
-                }
+        )));
+        default_label.add_css_class("dim-label");
+
+        let reset_button = Button::from_icon_name("edit-clear-symbolic");
+        reset_button.set_has_frame(false);
+        reset_button.set_tooltip_text(Some("Remove from the config and use the default"));
+
+        let widget = widget.clone();
+        reset_button.connect_clicked(move |button| {
+            let changed_before = changed_options.borrow().get(&key).cloned();
+            history.borrow_mut().set_replaying(true);
+            if let Some(default) = &default {
+                set_widget_value(&widget, default);
             }
+            history.borrow_mut().set_replaying(false);
+
+            let value = widget_value(&widget).unwrap_or_default();
+            history
+                .borrow_mut()
+                .record_unset(key.clone(), &value, changed_before);
+            changed_options
+                .borrow_mut()
+                .insert(key.clone(), UNSET.to_string());
+            notify_changes(button);
+        });
 
-            return value;
+        row.append(&default_label);
+        row.append(&reset_button);
+    }
+
+    pub fn extract_value(&self, config: &HyprlandConfig, category: &str, name: &str) -> String {
+        let (section, key) = config_lines::option_path(category, name);
+        config_lines::locate_option(config, &section, &key)
+            .map(|location| location.value)
+            .unwrap_or_default()
+    }
+}
+
+/// Store a widget change in `changed_options` and the undo history, unless the change
+/// comes from an undo/redo replaying an earlier value or leaves the value as it was.
+fn record_change(
+    widget: &impl IsA<Widget>,
+    changed_options: &Rc<RefCell<HashMap<(String, String), String>>>,
+    history: &Rc<RefCell<EditHistory>>,
+    key: &(String, String),
+    new_value: String,
+) {
+    {
+        let history = history.borrow();
+        if history.is_replaying() || history.value(key) == Some(new_value.as_str()) {
+            return;
         }
+    }
+    {
+        let mut changes = changed_options.borrow_mut();
+        history
+            .borrow_mut()
+            .record(key.clone(), &new_value, changes.get(key).cloned());
+        changes.insert(key.clone(), new_value);
+    }
+    notify_changes(widget);
+}
 
-        if parts.len() > 1 {
-            if let Some(&(parent_start, parent_end)) = config.sections.get(category)
-                && parent_start < config.content.len()
-                && parent_end < config.content.len()
-            {
-                let subsection = format!("{} {{", parts[0]);
-                let mut in_subsection = false;
+/// Let the window know `changed_options` changed, from any widget inside it.
+pub fn notify_changes(widget: &impl IsA<Widget>) {
+    // Only fails while the widget isn't part of the window yet
+    let _ = widget.activate_action(CHANGES_ACTION, None);
+}
 
-                for line in &config.content[parent_start..=parent_end] {
-                    let trimmed = line.trim();
+/// Highlight a row whose value differs from the file.
+pub fn set_modified(row: &impl IsA<Widget>, modified: bool) {
+    if modified {
+        row.add_css_class("modified");
+    } else {
+        row.remove_css_class("modified");
+    }
+}
 
-                    if trimmed == subsection {
-                        in_subsection = true;
-                        continue;
-                    }
+/// Highlight a row whose pending edit clashes with a change made on disk, explaining
+/// the clash in its tooltip.
+pub fn set_conflict(row: &impl IsA<Widget>, tooltip: &str) {
+    row.add_css_class("conflict");
+    row.set_tooltip_text(Some(tooltip));
+}
 
-                    if in_subsection {
-                        if trimmed == "}" {
-                            break;
-                        }
-
-                        if trimmed.starts_with(parts[1])
-                            && trimmed[parts[1].len()..].trim_start().starts_with('=')
-                            && let Some(val) = trimmed.split('=').nth(1)
-                        {
-                            value = val.trim().to_string();
-                            break;
-                        }
-                    }
-                }
-            }
-        } else if let Some(&(start, end)) = config.sections.get(category)
-            && start < config.content.len()
-            && end < config.content.len()
-        {
-            for line in &config.content[start..=end] {
-                let trimmed = line.trim();
-                if trimmed.starts_with(name)
-                    && trimmed[name.len()..].trim_start().starts_with('=')
-                    && let Some(val) = line.split('=').nth(1)
-                {
-                    value = val.trim().to_string();
-                    break;
-                }
-            }
+/// Show a value in an option widget, ignoring values the widget can't represent.
+pub fn set_widget_value(widget: &Widget, value: &str) {
+    if let Some(spin_button) = widget.downcast_ref::<SpinButton>() {
+        if let Ok(float_value) = value.parse::<f64>() {
+            spin_button.set_value(float_value);
         }
-
-        if value.is_empty() {
-            for idx in 0..config.sourced_content.len() {
-                let section_key = format!("{}_{}", category, idx);
-                if let Some(&(start, end)) = config.sourced_sections.get(&section_key) {
-                    let sourced = &config.sourced_content[idx];
-                    if start < sourced.len() && end < sourced.len() {
-                        if parts.len() > 1 {
-                            let subsection = format!("{} {{", parts[0]);
-                            let mut in_subsection = false;
-
-                            for line in &sourced[start..=end] {
-                                let trimmed = line.trim();
-
-                                if trimmed == subsection {
-                                    in_subsection = true;
-                                    continue;
-                                }
-
-                                if in_subsection {
-                                    if trimmed == "}" {
-                                        break;
-                                    }
-
-                                    if trimmed.starts_with(parts[1])
-                                        && trimmed[parts[1].len()..].trim_start().starts_with('=')
-                                        && let Some(val) = trimmed.split('=').nth(1)
-                                    {
-                                        value = val.trim().to_string();
-                                        break;
-                                    }
-                                }
-                            }
-                        } else {
-                            for line in &sourced[start..=end] {
-                                let trimmed = line.trim();
-                                if trimmed.starts_with(name)
-                                    && trimmed[name.len()..].trim_start().starts_with('=')
-                                    && let Some(val) = line.split('=').nth(1)
-                                {
-                                    value = val.trim().to_string();
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-                if !value.is_empty() {
-                    break;
-                }
+    } else if let Some(entry) = widget.downcast_ref::<Entry>() {
+        entry.set_text(value);
+    } else if let Some(switch) = widget.downcast_ref::<Switch>() {
+        switch.set_active(is_true(value));
+    } else if let Some(color_button) = widget.downcast_ref::<ColorButton>() {
+        let dummy_config = HyprlandConfig::new();
+        if let Some((red, green, blue, alpha)) = dummy_config.parse_color(value) {
+            color_button.set_rgba(&gdk::RGBA::new(red, green, blue, alpha));
+        }
+    } else if let Some(dropdown) = widget.downcast_ref::<gtk::DropDown>() {
+        let model = dropdown.model().unwrap();
+        for i in 0..model.n_items() {
+            if let Some(item) = model.item(i)
+                && let Some(string_object) = item.downcast_ref::<gtk::StringObject>()
+                && string_object.string() == value
+            {
+                dropdown.set_selected(i);
+                break;
             }
         }
+    }
+}
+
+/// Hyprland accepts `true`/`false`, `yes`/`no`, `on`/`off` and `1`/`0` for booleans.
+fn is_true(value: &str) -> bool {
+    matches!(value.trim(), "true" | "yes" | "on" | "1")
+}
 
-        value
+/// The current value of an option widget, formatted the way it is written to the config.
+pub fn widget_value(widget: &Widget) -> Option<String> {
+    if let Some(spin_button) = widget.downcast_ref::<SpinButton>() {
+        Some(spin_button.value().to_string())
+    } else if let Some(entry) = widget.downcast_ref::<Entry>() {
+        Some(entry.text().to_string())
+    } else if let Some(switch) = widget.downcast_ref::<Switch>() {
+        Some(switch.is_active().to_string())
+    } else if let Some(color_button) = widget.downcast_ref::<ColorButton>() {
+        Some(format_rgba(&color_button.rgba()))
+    } else if let Some(dropdown) = widget.downcast_ref::<gtk::DropDown>() {
+        dropdown
+            .selected_item()
+            .and_then(|item| item.downcast::<gtk::StringObject>().ok())
+            .map(|obj| obj.string().to_string())
+    } else {
+        None
     }
 }
+
+pub fn format_rgba(color: &gdk::RGBA) -> String {
+    format!(
+        "rgba({:02X}{:02X}{:02X}{:02X})",
+        (color.red() * 255.0) as u8,
+        (color.green() * 255.0) as u8,
+        (color.blue() * 255.0) as u8,
+        (color.alpha() * 255.0) as u8
+    )
+}